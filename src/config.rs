@@ -0,0 +1,273 @@
+use colored::Color;
+use lisp_parser::Broadcast;
+use rustyline::EditMode;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+const DEFAULT_HISTORY_SIZE: usize = 1000;
+
+const USAGE: &str =
+    "Usage: sauce [--config PATH] [--color auto|always|never] [--max-depth N] [--broadcast strict|fill|cycle] [--history-file PATH] [--history-size N]";
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(format!("color must be auto, always or never, got {}", mode)),
+        }
+    }
+}
+
+/// REPL settings. Defaults are overridden by `~/.saucerc`, which is in turn
+/// overridden by command line flags.
+pub struct Options {
+    pub prompt: String,
+    pub color: ColorMode,
+    pub prompt_color: Color,
+    pub error_color: Color,
+    pub edit_mode: EditMode,
+    pub history_file: Option<PathBuf>,
+    pub history_size: usize,
+    pub init_file: Option<PathBuf>,
+    pub broadcast: Broadcast,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            prompt: "s".to_string(),
+            color: ColorMode::Auto,
+            prompt_color: Color::Red,
+            error_color: Color::Red,
+            edit_mode: EditMode::Emacs,
+            history_file: default_history_file(),
+            history_size: DEFAULT_HISTORY_SIZE,
+            init_file: config_home().map(|dir| dir.join("sauce").join("init.sauce")),
+            broadcast: Broadcast::Strict,
+        }
+    }
+}
+
+pub fn parse_args() -> Result<Options, String> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let flags = split_flags(&args)?;
+
+    let mut options = Options::default();
+    let config_file = match flags.iter().find(|(flag, _)| flag == "--config") {
+        Some((_, path)) => Some(PathBuf::from(path)),
+        None => home().map(|home| home.join(".saucerc")),
+    };
+    if let Some(path) = config_file {
+        // A missing config file just means the defaults are wanted.
+        if let Ok(contents) = fs::read_to_string(&path) {
+            options.apply_config(&contents).map_err(|err| format!("{}: {}", path.display(), err))?;
+        }
+    }
+
+    for (flag, value) in flags {
+        match flag.as_str() {
+            "--config" => {}
+            "--color" => options.color = value.parse()?,
+            "--max-depth" => lisp_parser::set_max_depth(parse_number(&flag, &value)?),
+            "--broadcast" => options.broadcast = value.parse()?,
+            "--history-file" => options.history_file = Some(PathBuf::from(value)),
+            "--history-size" => options.history_size = parse_number(&flag, &value)?,
+            _ => return Err(format!("Unknown option: {}\n{}", flag, USAGE)),
+        }
+    }
+    Ok(options)
+}
+
+impl Options {
+    /// In auto mode, color is only used on a terminal and only when
+    /// `NO_COLOR` (https://no-color.org) isn't set.
+    pub fn use_color(&self) -> bool {
+        match self.color {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => {
+                let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                std::io::stdout().is_terminal() && !no_color
+            }
+        }
+    }
+
+    /// Reads `key = value` lines. Blank lines and lines starting with `#`
+    /// are ignored, and values may be wrapped in double quotes.
+    fn apply_config(&mut self, contents: &str) -> Result<(), String> {
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected key = value", number + 1))?;
+            let key = key.trim();
+            let value = value.trim();
+            let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+            let error = |message: &str| format!("line {}: {} {}", number + 1, key, message);
+            match key {
+                "prompt" => self.prompt = value.to_string(),
+                "color" => self.color = value.parse().map_err(|err| format!("line {}: {}", number + 1, err))?,
+                "prompt-color" => self.prompt_color = value.parse().map_err(|_| error("is not a color"))?,
+                "error-color" => self.error_color = value.parse().map_err(|_| error("is not a color"))?,
+                "edit-mode" => {
+                    self.edit_mode = match value {
+                        "emacs" => EditMode::Emacs,
+                        "vi" => EditMode::Vi,
+                        _ => return Err(error("must be vi or emacs")),
+                    }
+                }
+                "history-size" => self.history_size = value.parse().map_err(|_| error("must be a number"))?,
+                "history-file" => self.history_file = Some(expand_home(value)),
+                "init" => self.init_file = Some(expand_home(value)),
+                "max-depth" => lisp_parser::set_max_depth(value.parse().map_err(|_| error("must be a number"))?),
+                "broadcast" => self.broadcast = value.parse().map_err(|err| format!("line {}: {}", number + 1, err))?,
+                _ => return Err(format!("line {}: unknown setting {}", number + 1, key)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Pairs each `--flag` with its value, accepting both `--flag value` and
+/// `--flag=value`.
+fn split_flags(args: &[String]) -> Result<Vec<(String, String)>, String> {
+    let mut flags = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg.clone(), args.next().cloned()),
+        };
+        match value {
+            Some(value) => flags.push((flag, value)),
+            None => return Err(format!("{} expects a value\n{}", flag, USAGE)),
+        }
+    }
+    Ok(flags)
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<usize, String> {
+    value.parse().map_err(|_| format!("{} expects a number", flag))
+}
+
+fn home() -> Option<PathBuf> {
+    env::var_os("HOME").map(PathBuf::from)
+}
+
+/// Paths in the config file aren't seen by a shell, so expand `~/` here.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), home()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Reads an XDG base directory variable.
+fn xdg_dir(var: &str, default: &str) -> Option<PathBuf> {
+    base_dir(env::var_os(var), home(), default)
+}
+
+/// The directory an XDG variable set to `value` names, falling back to
+/// `default` under the home directory when it is unset or not absolute, as
+/// the spec asks.
+fn base_dir(value: Option<OsString>, home: Option<PathBuf>, default: &str) -> Option<PathBuf> {
+    match value.map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => Some(dir),
+        _ => home.map(|home| home.join(default)),
+    }
+}
+
+fn config_home() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// `$XDG_STATE_HOME/sauce/history.txt`, where `XDG_STATE_HOME` defaults to
+/// `~/.local/state`.
+fn default_history_file() -> Option<PathBuf> {
+    xdg_dir("XDG_STATE_HOME", ".local/state").map(|dir| dir.join("sauce").join("history.txt"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_lives_under_the_xdg_state_directory() {
+        let home = Some(PathBuf::from("/home/sauce"));
+        let state = |value: Option<&str>| base_dir(value.map(OsString::from), home.clone(), ".local/state");
+        assert_eq!(state(Some("/var/state")), Some(PathBuf::from("/var/state")));
+        // Relative paths are ignored, as the spec asks.
+        assert_eq!(state(Some("state")), Some(PathBuf::from("/home/sauce/.local/state")));
+        assert_eq!(state(None), Some(PathBuf::from("/home/sauce/.local/state")));
+        assert_eq!(base_dir(None, None, ".local/state"), None);
+        assert!(default_history_file().is_none_or(|path| path.ends_with("sauce/history.txt")));
+    }
+
+    #[test]
+    fn config_settings_override_the_defaults() {
+        let mut options = Options::default();
+        let config = "# my settings\n\nprompt = \"> \"\nprompt-color = blue\nedit-mode = vi\nhistory-size = 50\ninit = /tmp/init.sauce\nbroadcast = fill\n";
+        assert_eq!(options.apply_config(config), Ok(()));
+        assert_eq!(options.prompt, "> ");
+        assert_eq!(options.prompt_color, Color::Blue);
+        assert_eq!(options.error_color, Color::Red);
+        assert_eq!(options.edit_mode, EditMode::Vi);
+        assert_eq!(options.history_size, 50);
+        assert_eq!(options.init_file, Some(PathBuf::from("/tmp/init.sauce")));
+        assert_eq!(options.broadcast, Broadcast::Fill);
+    }
+
+    #[test]
+    fn config_paths_may_start_at_home() {
+        let mut options = Options::default();
+        assert_eq!(options.apply_config("history-file = ~/.sauce/history"), Ok(()));
+        assert_eq!(options.history_file, home().map(|home| home.join(".sauce/history")));
+    }
+
+    #[test]
+    fn config_errors_give_the_line_number() {
+        let mut options = Options::default();
+        assert_eq!(options.apply_config("prompt = a\nfont = mono"), Err("line 2: unknown setting font".to_string()));
+        assert_eq!(options.apply_config("edit-mode = nano"), Err("line 1: edit-mode must be vi or emacs".to_string()));
+        assert_eq!(options.apply_config("\nhistory-size = lots"), Err("line 2: history-size must be a number".to_string()));
+        assert_eq!(options.apply_config("error-color = plaid"), Err("line 1: error-color is not a color".to_string()));
+        assert_eq!(options.apply_config("prompt"), Err("line 1: expected key = value".to_string()));
+    }
+
+    #[test]
+    fn flags_take_their_value_either_way() {
+        let args: Vec<String> = ["--color=never", "--history-size", "5"].iter().map(|arg| arg.to_string()).collect();
+        let expected = vec![("--color".to_string(), "never".to_string()), ("--history-size".to_string(), "5".to_string())];
+        assert_eq!(split_flags(&args), Ok(expected));
+        let missing = split_flags(&["--color".to_string()]).unwrap_err();
+        assert!(missing.starts_with("--color expects a value\n"), "{}", missing);
+    }
+
+    #[test]
+    fn color_can_be_forced_on_or_off() {
+        assert_eq!("always".parse(), Ok(ColorMode::Always));
+        assert_eq!("rainbow".parse::<ColorMode>(), Err("color must be auto, always or never, got rainbow".to_string()));
+        let mut options = Options::default();
+        assert_eq!(options.apply_config("color = always"), Ok(()));
+        assert!(options.use_color());
+        assert_eq!(options.apply_config("color = \"never\""), Ok(()));
+        assert!(!options.use_color());
+    }
+}
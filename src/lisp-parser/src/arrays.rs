@@ -0,0 +1,323 @@
+use std::fmt;
+
+use crate::builtins::{arity, to_primitive, Builtin};
+use crate::lazy::Walk;
+use crate::native::{mismatch, Apply, HigherOrder};
+use crate::AstNode::{self, Function, NativeFn, Terms, Value};
+use crate::{interrupt, EvalError, Primitive};
+
+/// A rectangular array of two or more dimensions, stored as its shape and
+/// its items in row-major order. One-dimensional arrays are `Vector`s.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Array {
+    shape: Vec<usize>,
+    items: Vec<Primitive>,
+}
+
+impl Array {
+    /// `None` unless there are as many items as the shape calls for, and its
+    /// lengths multiply without overflowing.
+    pub fn new(shape: Vec<usize>, items: Vec<Primitive>) -> Option<Array> {
+        extent(&shape)?;
+        (shape.iter().product::<usize>() == items.len()).then_some(Array { shape, items })
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn items(&self) -> &[Primitive] {
+        &self.items
+    }
+
+    /// Reads nested vectors as an array, if they are rectangular. Anything
+    /// that isn't a vector is an array of rank 0.
+    pub(crate) fn from_nested(value: &Primitive) -> Option<Array> {
+        match value {
+            Primitive::Array(array) => Some(array.clone()),
+            Primitive::Vector(rows) => {
+                let rows = rows.iter().map(Array::from_nested).collect::<Option<Vec<_>>>()?;
+                let cell = rows.first().map(|row| row.shape.clone()).unwrap_or_default();
+                if rows.iter().any(|row| row.shape != cell) {
+                    return None;
+                }
+                let shape = [vec![rows.len()], cell].concat();
+                Some(Array { shape, items: rows.into_iter().flat_map(|row| row.items).collect() })
+            }
+            scalar => Some(Array { shape: vec![], items: vec![scalar.clone()] }),
+        }
+    }
+
+    /// The array as nested vectors, which is what the operators broadcast
+    /// over.
+    pub(crate) fn to_nested(&self) -> Primitive {
+        nest(&self.shape, &self.items)
+    }
+
+    /// The simplest primitive that holds the array: a scalar, a `Vector` or
+    /// an `Array`.
+    pub(crate) fn into_primitive(self) -> Primitive {
+        match self.shape.len() {
+            0 => self.items.into_iter().next().expect("an array of rank 0 has one item"),
+            1 => Primitive::Vector(self.items),
+            _ => Primitive::Array(self),
+        }
+    }
+
+    /// The items along the first axis, each an array of one rank lower.
+    pub(crate) fn rows(&self) -> Vec<Primitive> {
+        match self.to_nested() {
+            Primitive::Vector(rows) => rows,
+            scalar => vec![scalar],
+        }
+    }
+
+    /// Reverses the order of the axes, so rows become columns.
+    fn transpose(&self) -> Array {
+        let shape: Vec<usize> = self.shape.iter().rev().copied().collect();
+        let strides = strides(&self.shape);
+        let items = (0..self.items.len())
+            .map(|index| {
+                // Axis `axis` of the result is axis `rank - 1 - axis` here.
+                let mut rest = index;
+                let mut offset = 0;
+                for (axis, length) in shape.iter().enumerate().rev() {
+                    offset += (rest % length) * strides[shape.len() - 1 - axis];
+                    rest /= length;
+                }
+                self.items[offset].clone()
+            })
+            .collect();
+        Array { shape, items }
+    }
+}
+
+/// Prints as nested vectors.
+impl fmt::Display for Array {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_nested())
+    }
+}
+
+fn nest(shape: &[usize], items: &[Primitive]) -> Primitive {
+    match shape.split_first() {
+        None => items[0].clone(),
+        Some((&length, cell)) => {
+            let size = cell.iter().product::<usize>();
+            Primitive::Vector((0..length).map(|i| nest(cell, &items[i * size..(i + 1) * size])).collect())
+        }
+    }
+}
+
+/// The product of the lengths other than zero, unless it overflows. It is at
+/// least the size of the array and of any of its cells, so once it is known
+/// to fit, so do they.
+fn extent(shape: &[usize]) -> Option<usize> {
+    shape.iter().filter(|&&length| length > 0).try_fold(1usize, |extent, &length| extent.checked_mul(length))
+}
+
+/// How far apart neighbouring items along each axis are in row-major order.
+fn strides(shape: &[usize]) -> Vec<usize> {
+    let mut strides = vec![1; shape.len()];
+    for axis in (0..shape.len().saturating_sub(1)).rev() {
+        strides[axis] = strides[axis + 1] * shape[axis + 1];
+    }
+    strides
+}
+
+/// Replaces arrays, including ones inside vectors, with nested vectors.
+pub(crate) fn flatten_arrays(value: Primitive) -> Primitive {
+    match value {
+        Primitive::Array(array) => array.to_nested(),
+        Primitive::Vector(items) => Primitive::Vector(items.into_iter().map(flatten_arrays).collect()),
+        other => other,
+    }
+}
+
+/// Turns nested vectors back into an array, if they are rectangular and
+/// have at least two dimensions.
+pub(crate) fn shape_arrays(value: Primitive) -> Primitive {
+    match Array::from_nested(&value) {
+        Some(array) if array.shape.len() >= 2 => Primitive::Array(array),
+        _ => value,
+    }
+}
+
+/// J's array primitives, by name and by their J spelling.
+pub(crate) const ARRAY_BUILTINS: &[(&str, Builtin)] = &[
+    ("iota", iota),
+    ("i.", iota),
+    ("reshape", reshape),
+    ("shape", shape),
+    ("$", shape_or_reshape),
+    ("transpose", transpose),
+];
+
+/// The adverbs behind `f/` and `f\`, and the table that `f/` makes of two
+/// lists.
+pub(crate) const ADVERBS: &[(&str, HigherOrder)] = &[("insert", insert), ("scan", scan), ("outer", outer)];
+
+/// `(iota 5)` is `[0, 1, 2, 3, 4]`, and `(iota 2 3)` counts from 0 in an
+/// array of that shape.
+fn iota(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("iota", args, 1, usize::MAX)?;
+    let shape = dimensions("iota", args)?;
+    let count = shape.iter().product::<usize>() as i64;
+    let items = (0..count).map(Primitive::Integer).collect();
+    Ok(Value(Array { shape, items }.into_primitive()))
+}
+
+/// `(reshape shape x)` arranges the items of `x` in a new shape, repeating
+/// them if there aren't enough.
+fn reshape(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("reshape", args, 2, 2)?;
+    let shape = dimensions("reshape", &args[..1])?;
+    let source = to_array("reshape", &args[1])?;
+    let count = shape.iter().product::<usize>();
+    if count > 0 && source.items.is_empty() {
+        return Err(EvalError::InvalidForm("Cannot reshape an empty array".to_string()));
+    }
+    let items = source.items.into_iter().cycle().take(count).collect();
+    Ok(Value(Array { shape, items }.into_primitive()))
+}
+
+/// The length of each axis. Scalars have an empty shape.
+fn shape(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("shape", args, 1, 1)?;
+    let shape = to_array("shape", &args[0])?.shape;
+    Ok(Value(Primitive::Vector(shape.into_iter().map(|length| Primitive::Integer(length as i64)).collect())))
+}
+
+/// As in J, `$` is `shape` given one argument and `reshape` given two.
+fn shape_or_reshape(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    match args.len() {
+        1 => shape(args),
+        _ => reshape(args),
+    }
+}
+
+fn transpose(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("transpose", args, 1, 1)?;
+    Ok(Value(to_array("transpose", &args[0])?.transpose().into_primitive()))
+}
+
+/// Lists, vectors and arrays, nested to any depth, as long as they are
+/// rectangular.
+fn to_array(name: &str, node: &AstNode) -> Result<Array, EvalError> {
+    Array::from_nested(&to_primitive(node, name)?)
+        .ok_or_else(|| EvalError::InvalidForm(format!("{} expects a rectangular array, got {}", name, node)))
+}
+
+/// The largest array `iota` and `reshape` make, in items. Empty arrays count
+/// their lengths other than zero, since they print as that many empty
+/// vectors.
+const MAX_ITEMS: usize = 1 << 24;
+
+/// A shape, given either as separate lengths or as one list of them.
+fn dimensions(name: &str, args: &[AstNode]) -> Result<Vec<usize>, EvalError> {
+    let lengths = match args {
+        [Terms(items)] => items.clone(),
+        [Value(Primitive::Vector(items))] => items.iter().cloned().map(Value).collect(),
+        _ => args.to_vec(),
+    };
+    let shape = lengths
+        .iter()
+        .map(|length| match length {
+            Value(Primitive::Integer(length)) if *length >= 0 => Ok(*length as usize),
+            other => Err(EvalError::InvalidForm(format!("{} expects lengths that are non-negative integers, got {}", name, other))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match extent(&shape) {
+        Some(extent) if extent <= MAX_ITEMS => Ok(shape),
+        _ => Err(EvalError::InvalidForm(format!("{} can't make an array of more than {} items", name, MAX_ITEMS))),
+    }
+}
+
+/// `(insert f)`, written `f/`, is the verb that puts `f` between the items
+/// of a list, so `+/ 1 2 3` is `1 + 2 + 3`. Like J it groups from the right:
+/// `-/ 1 2 3` is `1 - (2 - 3)`.
+fn insert(args: &[AstNode], _: &mut Apply) -> Result<AstNode, EvalError> {
+    adverb("insert", "/", args, insert_into)
+}
+
+/// `(scan f)`, written `f\`, is the verb that inserts `f` into every prefix
+/// of a list, so `+\ 1 2 3` is the running total `1 3 6`.
+fn scan(args: &[AstNode], _: &mut Apply) -> Result<AstNode, EvalError> {
+    adverb("scan", "\\", args, scan_over)
+}
+
+fn adverb(name: &str, symbol: &str, args: &[AstNode], derived: HigherOrder) -> Result<AstNode, EvalError> {
+    arity(name, args, 1, 1)?;
+    let verb = match &args[0] {
+        Function { lambda, .. } => lambda.name.clone(),
+        NativeFn(native) => native.name.clone(),
+        other => return Err(mismatch("a function", other)),
+    };
+    let name = format!("{}{}", verb, symbol);
+    Ok(NativeFn(crate::NativeFn::partial(&name, derived, args.to_vec())))
+}
+
+/// Like J, `f/` given two lists makes a table instead: `*/ [1 2] [3 4]` is
+/// `(outer * [1 2] [3 4])`.
+fn insert_into(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    if let [_, x, y] = args {
+        if is_list(x) && is_list(y) {
+            return outer(args, apply);
+        }
+    }
+    let items = operands(&args[1..], apply)?;
+    fold_right(&args[0], &items, apply)
+}
+
+/// Each prefix groups from the right, so it can't build on the one before it
+/// and scanning `n` items applies `f` about `n * n / 2` times, as in J.
+fn scan_over(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    let items = operands(&args[1..], apply)?;
+    let prefixes = (1..=items.len()).map(|length| fold_right(&args[0], &items[..length], apply));
+    Ok(Terms(prefixes.collect::<Result<_, _>>()?))
+}
+
+/// `(outer f xs ys)` is the table of `(f x y)` for every `x` in `xs` and `y`
+/// in `ys`, so `(outer * [1 2 3] [1 2 3])` is a multiplication table.
+fn outer(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("outer", args, 3, 3)?;
+    let xs = Walk::new(&args[1])?.collect(apply)?;
+    let ys = Walk::new(&args[2])?.collect(apply)?;
+    let mut rows = vec![];
+    for x in xs {
+        interrupt::check()?;
+        let row = ys.iter().map(|y| apply(&args[0], vec![x.clone(), y.clone()])).collect::<Result<_, _>>()?;
+        rows.push(Terms(row));
+    }
+    let table = Terms(rows);
+    // A table of plain values is an array, otherwise it stays a list.
+    match to_primitive(&table, "outer") {
+        Ok(value) => Ok(Value(shape_arrays(value))),
+        Err(_) => Ok(table),
+    }
+}
+
+fn is_list(node: &AstNode) -> bool {
+    matches!(node, Terms(_) | Value(Primitive::Vector(_) | Primitive::Array(_)) | AstNode::LazySeq(_))
+}
+
+/// A verb applies to a single list it is given, or to all of its arguments:
+/// `+/ [1 2 3]` is the same as `+/ 1 2 3`.
+fn operands(args: &[AstNode], apply: &mut Apply) -> Result<Vec<AstNode>, EvalError> {
+    match args {
+        [list] if is_list(list) => Walk::new(list)?.collect(apply),
+        _ => Ok(args.to_vec()),
+    }
+}
+
+/// An empty list gives `(f)`, the identity of `+` and `*`.
+fn fold_right(f: &AstNode, items: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    let mut items = items.iter().rev().cloned();
+    match items.next() {
+        Some(last) => items.try_fold(last, |accumulator, item| {
+            interrupt::check()?;
+            apply(f, vec![item, accumulator])
+        }),
+        None => apply(f, vec![]),
+    }
+}
@@ -0,0 +1,102 @@
+use std::cell::Cell;
+
+use crate::{OperationError, Primitive};
+
+/// How arithmetic pairs up the items of two vectors of different lengths.
+/// Vectors of the same length are always combined item by item, and a
+/// scalar is combined with every item of a vector.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum Broadcast {
+    /// Different lengths are an error. This is the default.
+    #[default]
+    Strict,
+    /// The shorter vector is padded to the length of the longer, as in J:
+    /// with 0 for numbers, `""` for strings and padded vectors for vectors.
+    Fill,
+    /// The shorter vector is repeated, as in R.
+    Cycle,
+}
+
+impl std::str::FromStr for Broadcast {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "strict" => Ok(Broadcast::Strict),
+            "fill" => Ok(Broadcast::Fill),
+            "cycle" => Ok(Broadcast::Cycle),
+            _ => Err(format!("broadcast must be strict, fill or cycle, got {}", mode)),
+        }
+    }
+}
+
+thread_local! {
+    // The mode of the interpreter evaluating on this thread, if any.
+    static BROADCAST: Cell<Broadcast> = const { Cell::new(Broadcast::Strict) };
+}
+
+/// Runs `f` with arithmetic broadcasting in `mode`. Outside of it, vectors
+/// of different lengths are an error.
+pub(crate) fn with_broadcast<T>(mode: Broadcast, f: impl FnOnce() -> T) -> T {
+    struct Restore(Broadcast);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            BROADCAST.with(|current| current.set(self.0));
+        }
+    }
+    let _restore = Restore(BROADCAST.with(|current| current.replace(mode)));
+    f()
+}
+
+/// Applies `op` to scalars, and item by item to vectors, nested to any
+/// depth. The left operand stays on the left, so `(- 10 [1 2])` is
+/// `[9, 8]`.
+pub(crate) fn elementwise(
+    x: Primitive,
+    y: Primitive,
+    op: fn(Primitive, Primitive) -> Result<Primitive, OperationError>,
+) -> Result<Primitive, OperationError> {
+    let items: Result<_, _> = match (x, y) {
+        (Primitive::Vector(xs), Primitive::Vector(ys)) => {
+            let (xs, ys) = match_lengths(xs, ys)?;
+            xs.into_iter().zip(ys).map(|(x, y)| elementwise(x, y, op)).collect()
+        }
+        (Primitive::Vector(xs), y) => xs.into_iter().map(|x| elementwise(x, y.clone(), op)).collect(),
+        (x, Primitive::Vector(ys)) => ys.into_iter().map(|y| elementwise(x.clone(), y, op)).collect(),
+        (x, y) => return op(x, y),
+    };
+    Ok(Primitive::Vector(items?))
+}
+
+fn match_lengths(mut xs: Vec<Primitive>, mut ys: Vec<Primitive>) -> Result<(Vec<Primitive>, Vec<Primitive>), OperationError> {
+    if xs.len() == ys.len() {
+        return Ok((xs, ys));
+    }
+    let (shorter, longer) = if xs.len() < ys.len() { (&mut xs, &ys) } else { (&mut ys, &xs) };
+    match BROADCAST.with(Cell::get) {
+        Broadcast::Fill => {
+            let padding: Vec<Primitive> = longer[shorter.len()..].iter().map(fill).collect();
+            shorter.extend(padding);
+        }
+        // There is nothing to repeat in an empty vector.
+        Broadcast::Cycle if !shorter.is_empty() => {
+            let cycled = shorter.iter().cycle().take(longer.len()).cloned().collect();
+            *shorter = cycled;
+        }
+        _ => {
+            return Err(OperationError::from(
+                format!("Cannot combine vectors of lengths {} and {}", xs.len(), ys.len()).as_str(),
+            ))
+        }
+    }
+    Ok((xs, ys))
+}
+
+/// The padding that goes opposite `item`.
+fn fill(item: &Primitive) -> Primitive {
+    match item {
+        Primitive::Str(_) => Primitive::Str(String::new()),
+        Primitive::Vector(items) => Primitive::Vector(items.iter().map(fill).collect()),
+        _ => Primitive::Integer(0),
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::arrays::{self, ADVERBS, ARRAY_BUILTINS};
+use crate::indexing::INDEX_BUILTINS;
+use crate::native::mismatch;
+use crate::operators::{self, OperationError};
+use crate::pattern::REGEX_BUILTINS;
+use crate::sequences::SEQUENCE_BUILTINS;
+use crate::strings::STRING_BUILTINS;
+use crate::AstNode::{self, Terms, Value};
+use crate::{is_truthy, EvalError, NativeFn, Primitive};
+
+type PrimitiveOp = fn(Vec<Primitive>) -> Result<Primitive, OperationError>;
+
+pub(crate) type Builtin = fn(&[AstNode]) -> Result<AstNode, EvalError>;
+
+/// Operators over numbers, strings and vectors. Lists passed to them are
+/// converted to vectors, so the operators broadcast over their elements.
+const PRIMITIVE_OPS: &[(&str, PrimitiveOp)] = &[
+    ("+", operators::add),
+    ("-", operators::subtract),
+    ("*", operators::multiply),
+    ("/", operators::divide),
+    ("^", operators::raise),
+    (">", operators::greater_than),
+    (">=", operators::greater_than_equal),
+    ("<", operators::less_than),
+    ("<=", operators::less_than_equal),
+    ("==", operators::equal),
+    ("!=", operators::not_equal),
+    ("|", operators::or),
+    ("&", operators::and),
+    ("#", operators::tally),
+    ("numerator", operators::numerator),
+    ("denominator", operators::denominator),
+    ("quot", operators::quot),
+    ("rem", operators::rem),
+    ("mod", operators::modulo),
+];
+
+const BUILTINS: &[(&str, Builtin)] = &[("list", list), ("count", count), ("not", not)];
+
+/// Every builtin function, by name. Adding a builtin only means adding it to
+/// one of the tables above, or to the string, regex, sequence, indexing and
+/// array tables in `strings.rs`, `pattern.rs`, `sequences.rs`, `indexing.rs`
+/// and `arrays.rs`.
+fn registry() -> &'static HashMap<&'static str, NativeFn> {
+    static REGISTRY: OnceLock<HashMap<&'static str, NativeFn>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut registry = HashMap::new();
+        for &(name, op) in PRIMITIVE_OPS {
+            let func = move |args: &[AstNode]| {
+                let values: Vec<Primitive> = args.iter().map(|arg| to_primitive(arg, name)).collect::<Result<_, _>>()?;
+                // The operators broadcast over nested vectors, so arrays are
+                // taken apart for them and the result put back together.
+                if !values.iter().any(contains_array) {
+                    return Ok(Value(op(values)?));
+                }
+                let values = values.into_iter().map(arrays::flatten_arrays).collect();
+                Ok(Value(arrays::shape_arrays(op(values)?)))
+            };
+            registry.insert(name, NativeFn::new(name, func));
+        }
+        for &(name, func) in BUILTINS.iter().chain(STRING_BUILTINS).chain(REGEX_BUILTINS).chain(ARRAY_BUILTINS) {
+            registry.insert(name, NativeFn::new(name, func));
+        }
+        for &(name, func) in SEQUENCE_BUILTINS.iter().chain(INDEX_BUILTINS).chain(ADVERBS) {
+            registry.insert(name, NativeFn::higher_order(name, func));
+        }
+        registry
+    })
+}
+
+/// The builtin bound to `name`, if there is one.
+pub(crate) fn lookup(name: &str) -> Option<AstNode> {
+    registry().get(name).cloned().map(AstNode::NativeFn)
+}
+
+/// Checks that a builtin was given between `min` and `max` arguments.
+pub(crate) fn arity(name: &str, args: &[AstNode], min: usize, max: usize) -> Result<(), EvalError> {
+    if args.len() < min || args.len() > max {
+        return Err(EvalError::Arity { name: name.to_string(), min, max, given: args.len() });
+    }
+    Ok(())
+}
+
+/// A value for the operators. Lists become vectors.
+pub(crate) fn to_primitive(node: &AstNode, name: &str) -> Result<Primitive, EvalError> {
+    match node {
+        Value(value) => Ok(value.clone()),
+        Terms(items) => Ok(Primitive::Vector(items.iter().map(|item| to_primitive(item, name)).collect::<Result<_, _>>()?)),
+        other => Err(OperationError::from(format!("Cannot apply {} to {}", name, other).as_str()).into()),
+    }
+}
+
+fn contains_array(value: &Primitive) -> bool {
+    match value {
+        Primitive::Array(_) => true,
+        Primitive::Vector(items) => items.iter().any(contains_array),
+        _ => false,
+    }
+}
+
+fn list(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    Ok(Terms(args.to_vec()))
+}
+
+fn count(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    let count = match args {
+        [Terms(items)] => items.len(),
+        [Value(Primitive::Vector(items))] => items.len(),
+        [Value(Primitive::Array(array))] => array.rows().len(),
+        [Value(Primitive::Str(s))] => s.chars().count(),
+        [other] => return Err(mismatch("a collection", other)),
+        _ => return Err(EvalError::Arity { name: "count".to_string(), min: 1, max: 1, given: args.len() }),
+    };
+    Ok(Value(Primitive::Integer(count as i64)))
+}
+
+fn not(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    match args {
+        [arg] => Ok(Value(Primitive::Integer(!is_truthy(arg) as i64))),
+        _ => Err(EvalError::Arity { name: "not".to_string(), min: 1, max: 1, given: args.len() }),
+    }
+}
@@ -0,0 +1,73 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::{AstNode, OperationError, Rule};
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum EvalError {
+    UnboundSymbol(String),
+    UnquoteOutsideQuasiquote,
+    SpliceOutsideList,
+    NotSpliceable(AstNode),
+    /// `max` is `usize::MAX` when any number of arguments from `min` up is
+    /// allowed.
+    Arity { name: String, min: usize, max: usize, given: usize },
+    InvalidForm(String),
+    Operation(OperationError),
+    StackOverflow { depth: usize, chain: Vec<String> },
+    Interrupted,
+    Parse(Box<pest::error::Error<Rule>>),
+    Io(String),
+    TypeMismatch { expected: String, found: Box<AstNode> },
+}
+
+impl Error for EvalError {}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::UnboundSymbol(name) => write!(f, "Unable to resolve symbol: {}", name),
+            EvalError::UnquoteOutsideQuasiquote => write!(f, "Unquote (~) used outside of a quasiquote"),
+            EvalError::SpliceOutsideList => write!(f, "Unquote-splicing (~@) used outside of a list"),
+            EvalError::NotSpliceable(node) => write!(f, "Cannot splice a non-list value: {}", node),
+            EvalError::Arity { name, min, max, given } => {
+                write!(f, "Wrong number of args ({}) passed to {}, expected ", given, name)?;
+                match *max {
+                    max if max == *min => write!(f, "{}", min),
+                    usize::MAX => write!(f, "at least {}", min),
+                    max => write!(f, "{} to {}", min, max),
+                }
+            }
+            EvalError::InvalidForm(message) => write!(f, "{}", message),
+            EvalError::Operation(err) => write!(f, "{}", err),
+            EvalError::StackOverflow { depth, chain } => {
+                write!(f, "Stack overflow: evaluation nested deeper than {} levels", depth)?;
+                // Only the innermost calls are interesting in a runaway
+                // recursion.
+                for name in chain.iter().rev().take(10) {
+                    write!(f, "\n    in {}", name)?;
+                }
+                if chain.len() > 10 {
+                    write!(f, "\n    ... {} more", chain.len() - 10)?;
+                }
+                Ok(())
+            }
+            EvalError::Interrupted => write!(f, "Interrupted"),
+            EvalError::Parse(err) => write!(f, "{}", err),
+            EvalError::Io(message) => write!(f, "{}", message),
+            EvalError::TypeMismatch { expected, found } => write!(f, "Expected {}, got {}", expected, found),
+        }
+    }
+}
+
+impl From<pest::error::Error<Rule>> for EvalError {
+    fn from(err: pest::error::Error<Rule>) -> Self {
+        EvalError::Parse(Box::new(err))
+    }
+}
+
+impl From<OperationError> for EvalError {
+    fn from(err: OperationError) -> Self {
+        EvalError::Operation(err)
+    }
+}
@@ -0,0 +1,132 @@
+use crate::arrays::shape_arrays;
+use crate::builtins::{arity, to_primitive};
+use crate::lazy::Walk;
+use crate::native::{Apply, HigherOrder};
+use crate::AstNode::{self, Terms, Value};
+use crate::{interrupt, EvalError, Primitive};
+
+/// Indexing and slicing. Indices count from 0, or back from the end when
+/// they are negative, so -1 is the last item. Strings are indexed by
+/// character.
+pub(crate) const INDEX_BUILTINS: &[(&str, HigherOrder)] = &[
+    ("nth", nth),
+    ("get", get),
+    ("subvec", subvec),
+    ("from", from),
+    ("{", from),
+];
+
+/// `(nth coll i)` is the item at `i`, and an error if there isn't one.
+/// `(nth coll i not-found)` returns `not-found` instead.
+fn nth(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("nth", args, 2, 3)?;
+    let index = i64::try_from(&args[1])?;
+    match (item_at(&args[0], index, apply)?, args.get(2)) {
+        (Some(item), _) => Ok(item),
+        (None, Some(not_found)) => Ok(not_found.clone()),
+        (None, None) => Err(out_of_range(index, &args[0], apply)),
+    }
+}
+
+/// Like `nth`, but a missing item is the empty list, or the default given
+/// as a third argument, rather than an error.
+fn get(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("get", args, 2, 3)?;
+    let index = i64::try_from(&args[1])?;
+    let default = args.get(2).cloned().unwrap_or(Terms(vec![]));
+    Ok(item_at(&args[0], index, apply)?.unwrap_or(default))
+}
+
+/// `(subvec coll start)` or `(subvec coll start end)` is the items from
+/// `start` up to but not including `end`, as the same kind of collection:
+/// a slice of a string is a string.
+fn subvec(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("subvec", args, 2, 3)?;
+    let items = Walk::new(&args[0])?.collect(apply)?;
+    let start = i64::try_from(&args[1])?;
+    let end = match args.get(2) {
+        Some(end) => i64::try_from(end)?,
+        None => items.len() as i64,
+    };
+    match (bound(start, items.len()), bound(end, items.len())) {
+        (Some(from), Some(to)) if from <= to => like(&args[0], items[from..to].to_vec()),
+        _ => Err(EvalError::InvalidForm(format!("Slice {} to {} is out of range for {} items", start, end, items.len()))),
+    }
+}
+
+/// J's from, written `{`: `({ 1 xs)` is the item at 1, and `({ [2 0] xs)`
+/// the items at 2 and 0, as the same kind of collection as `xs`.
+fn from(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("{", args, 2, 2)?;
+    let (indices, coll) = (&args[0], &args[1]);
+    if let Ok(index) = i64::try_from(indices) {
+        return item_at(coll, index, apply)?.ok_or_else(|| out_of_range(index, coll, apply));
+    }
+    let indices = Walk::new(indices)?.collect(apply)?;
+    let items = Walk::new(coll)?.collect(apply)?;
+    let selected = indices
+        .iter()
+        .map(|index| {
+            let index = i64::try_from(index)?;
+            position(index, items.len())
+                .map(|position| items[position].clone())
+                .ok_or_else(|| out_of_range(index, coll, apply))
+        })
+        .collect::<Result<_, _>>()?;
+    like(coll, selected)
+}
+
+/// The item at `index`, if there is one. Only as much of a lazy sequence as
+/// is needed is realized, unless `index` counts from the end.
+fn item_at(coll: &AstNode, index: i64, apply: &mut Apply) -> Result<Option<AstNode>, EvalError> {
+    if let (AstNode::LazySeq(_), Ok(skip)) = (coll, usize::try_from(index)) {
+        let mut walk = Walk::new(coll)?;
+        for _ in 0..skip {
+            interrupt::check()?;
+            if walk.next(apply)?.is_none() {
+                return Ok(None);
+            }
+        }
+        return walk.next(apply);
+    }
+    let items = Walk::new(coll)?.collect(apply)?;
+    Ok(position(index, items.len()).map(|position| items[position].clone()))
+}
+
+fn out_of_range(index: i64, coll: &AstNode, apply: &mut Apply) -> EvalError {
+    match Walk::new(coll).and_then(|walk| walk.collect(apply)) {
+        Ok(items) => EvalError::InvalidForm(format!("Index {} is out of range for {} items", index, items.len())),
+        Err(err) => err,
+    }
+}
+
+/// Where `index` points in a collection of `length` items, if anywhere.
+fn position(index: i64, length: usize) -> Option<usize> {
+    bound(index, length).filter(|&position| position < length)
+}
+
+/// Like `position`, but also allows the end of the collection, for slices.
+fn bound(index: i64, length: usize) -> Option<usize> {
+    let position = if index < 0 { length as i64 + index } else { index };
+    usize::try_from(position).ok().filter(|&position| position <= length)
+}
+
+/// `items` as the same kind of collection as `coll`.
+fn like(coll: &AstNode, items: Vec<AstNode>) -> Result<AstNode, EvalError> {
+    let values = || items.iter().map(|item| to_primitive(item, "{")).collect::<Result<Vec<_>, _>>();
+    match coll {
+        // The items of a string are one-character strings.
+        Value(Primitive::Str(_)) => {
+            let mut s = String::new();
+            for item in values()? {
+                if let Primitive::Str(c) = item {
+                    s.push_str(&c);
+                }
+            }
+            Ok(Value(Primitive::Str(s)))
+        }
+        Value(Primitive::Vector(_)) => Ok(Value(Primitive::Vector(values()?))),
+        Value(Primitive::Array(_)) => Ok(Value(shape_arrays(Primitive::Vector(values()?)))),
+        _ => Ok(Terms(items)),
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::broadcast::with_broadcast;
+use crate::{apply, evaluate_top_level, expand, interrupt, lazy, parse, AstNode, Broadcast, EvalError, NativeFn};
+
+/// A Sauce interpreter with its own global variables, for embedding Sauce
+/// as a configuration or scripting language.
+///
+/// ```
+/// use lisp_parser::{AstNode, Interpreter, Primitive};
+///
+/// let mut sauce = Interpreter::new();
+/// sauce.eval_str("(defn double [x] (* x 2))").unwrap();
+/// sauce.set("answer", 21);
+/// sauce.register_fn("sqrt", |args| {
+///     let x = f64::try_from(&args[0])?;
+///     Ok(x.sqrt().into())
+/// });
+/// let result = sauce.eval_str("(sqrt (double answer))").unwrap();
+/// assert_eq!(result, AstNode::Value(Primitive::DoublePrecisionFloat(42f64.sqrt())));
+/// ```
+///
+/// Each interpreter has its own broadcasting mode (`set_broadcast`), but
+/// the evaluation depth limit (`set_max_depth`) and `interrupt` are shared
+/// by every interpreter in the process.
+#[derive(Debug, Clone, Default)]
+pub struct Interpreter {
+    globals: HashMap<String, AstNode>,
+    broadcast: Broadcast,
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter::default()
+    }
+
+    /// Parses, macro expands and evaluates `source`, returning the value of
+    /// the last form.
+    pub fn eval_str(&mut self, source: &str) -> Result<AstNode, EvalError> {
+        let ast = parse(source)?;
+        interrupt::reset();
+        let globals = &mut self.globals;
+        with_broadcast(self.broadcast, || {
+            let mut result = AstNode::Terms(vec![]);
+            // Each form is expanded only once the ones before it have run, so
+            // a macro can call the functions and read the globals defined
+            // above it.
+            for form in ast {
+                for form in expand(vec![form], globals)? {
+                    result = evaluate_top_level(form, globals)?;
+                }
+            }
+            Ok(result)
+        })
+    }
+
+    /// Computes up to `limit` items of each lazy sequence in `value`, so
+    /// that printing it shows them rather than `...`. Evaluation leaves lazy
+    /// sequences unrealized, since they may be infinite.
+    pub fn force(&mut self, value: &AstNode, limit: usize) -> Result<(), EvalError> {
+        let globals = &mut self.globals;
+        with_broadcast(self.broadcast, || lazy::force(value, limit, &mut |func, args| apply(func.clone(), args, globals)))
+    }
+
+    /// Parses and macro expands `source` without evaluating it. Macros it
+    /// defines are kept.
+    pub fn expand_str(&mut self, source: &str) -> Result<Vec<AstNode>, EvalError> {
+        let ast = parse(source)?;
+        with_broadcast(self.broadcast, || expand(ast, &mut self.globals))
+    }
+
+    pub fn eval_file<P: AsRef<Path>>(&mut self, path: P) -> Result<AstNode, EvalError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|err| EvalError::Io(format!("{}: {}", path.display(), err)))?;
+        self.eval_str(&source)
+    }
+
+    /// How arithmetic in this interpreter pairs up vectors of different
+    /// lengths. The default is `Broadcast::Strict`.
+    pub fn set_broadcast(&mut self, mode: Broadcast) {
+        self.broadcast = mode;
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AstNode> {
+        self.globals.get(name)
+    }
+
+    pub fn set<V: Into<AstNode>>(&mut self, name: &str, value: V) {
+        self.globals.insert(name.to_string(), value.into());
+    }
+
+    /// Makes a Rust function callable from Sauce as `name`. Use `TryFrom`
+    /// to read the arguments as Rust types and `.into()` to convert the
+    /// result back.
+    pub fn register_fn<F>(&mut self, name: &str, func: F)
+    where
+        F: Fn(&[AstNode]) -> Result<AstNode, EvalError> + Send + Sync + 'static,
+    {
+        self.set(name, AstNode::NativeFn(NativeFn::new(name, func)));
+    }
+
+    /// All global variables, including functions and macros.
+    pub fn globals(&self) -> &HashMap<String, AstNode> {
+        &self.globals
+    }
+}
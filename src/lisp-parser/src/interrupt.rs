@@ -0,0 +1,25 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::EvalError;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Asks the running evaluation to stop with `EvalError::Interrupted`. This
+/// only sets a flag, so it is safe to call from a signal handler or another
+/// thread. Returns whether an earlier interrupt was still waiting to be
+/// noticed, which means evaluation is stuck somewhere that doesn't check.
+pub fn interrupt() -> bool {
+    INTERRUPTED.swap(true, Ordering::SeqCst)
+}
+
+/// Forgets an interrupt that arrived while nothing was being evaluated.
+pub(crate) fn reset() {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+}
+
+pub(crate) fn check() -> Result<(), EvalError> {
+    if INTERRUPTED.swap(false, Ordering::SeqCst) {
+        return Err(EvalError::Interrupted);
+    }
+    Ok(())
+}
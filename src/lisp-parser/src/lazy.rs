@@ -0,0 +1,284 @@
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::vec;
+
+use crate::native::{mismatch, Apply};
+use crate::AstNode::{self, Terms, Value};
+use crate::{interrupt, EvalError, Primitive};
+
+type Thunk = Box<dyn FnOnce(&mut Apply) -> Result<AstNode, EvalError> + Send>;
+
+/// A sequence whose items are only computed when something asks for them,
+/// written with `lazy-seq` or returned by `range`, `iterate`, `map` and
+/// friends. Each item is computed at most once, and copies of a lazy
+/// sequence share what has been computed.
+#[derive(Clone)]
+pub struct LazySeq {
+    state: Arc<Mutex<State>>,
+}
+
+enum State {
+    Pending(Thunk),
+    // A Sauce function of no arguments, kept as a value rather than in a
+    // thunk so that `take_parts` can reach the scope it holds.
+    Delayed(AstNode),
+    Realizing,
+    Cons(AstNode, AstNode),
+    Empty,
+    Failed(EvalError),
+}
+
+impl LazySeq {
+    /// A sequence that is whatever `thunk` returns: a list, a vector, a
+    /// string or another lazy sequence.
+    pub(crate) fn new<F>(thunk: F) -> LazySeq
+    where
+        F: FnOnce(&mut Apply) -> Result<AstNode, EvalError> + Send + 'static,
+    {
+        LazySeq::with_state(State::Pending(Box::new(thunk)))
+    }
+
+    /// A sequence that is whatever calling `func` with no arguments returns.
+    pub(crate) fn delay(func: AstNode) -> LazySeq {
+        LazySeq::with_state(State::Delayed(func))
+    }
+
+    /// `first` followed by the sequence `rest`, which is left unrealized.
+    pub(crate) fn cons(first: AstNode, rest: AstNode) -> LazySeq {
+        LazySeq::with_state(State::Cons(first, rest))
+    }
+
+    fn with_state(state: State) -> LazySeq {
+        LazySeq { state: Arc::new(Mutex::new(state)) }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The first item and the rest of the sequence, or `None` if it's
+    /// empty. Realizes the first item if it hasn't been already.
+    pub(crate) fn step(&self, apply: &mut Apply) -> Result<Option<(AstNode, AstNode)>, EvalError> {
+        // The lock isn't held while the thunk runs, since the thunk may well
+        // look at other lazy sequences.
+        let state = std::mem::replace(&mut *self.state(), State::Realizing);
+        let thunk = match state {
+            State::Pending(thunk) => thunk,
+            State::Delayed(func) => Box::new(move |apply: &mut Apply| apply(&func, vec![])),
+            State::Realizing => {
+                return Err(EvalError::InvalidForm("A lazy sequence can't depend on its own items".to_string()))
+            }
+            done => {
+                let step = match &done {
+                    State::Cons(first, rest) => Ok(Some((first.clone(), rest.clone()))),
+                    State::Failed(err) => Err(err.clone()),
+                    _ => Ok(None),
+                };
+                *self.state() = done;
+                return step;
+            }
+        };
+        let step = thunk(apply).and_then(|seq| first_and_rest(seq, apply));
+        *self.state() = match &step {
+            Ok(Some((first, rest))) => State::Cons(first.clone(), rest.clone()),
+            Ok(None) => State::Empty,
+            Err(err) => State::Failed(err.clone()),
+        };
+        step
+    }
+
+    /// Moves the values this part of the sequence holds into `parts`, if
+    /// nothing else refers to it.
+    pub(crate) fn take_parts(&mut self, parts: &mut Vec<AstNode>) {
+        let state = match Arc::get_mut(&mut self.state) {
+            Some(state) => state.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner()),
+            None => return,
+        };
+        if matches!(state, State::Cons(..) | State::Delayed(_)) {
+            match std::mem::replace(state, State::Empty) {
+                State::Cons(first, rest) => parts.extend([first, rest]),
+                State::Delayed(func) => parts.push(func),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn first_and_rest(seq: AstNode, apply: &mut Apply) -> Result<Option<(AstNode, AstNode)>, EvalError> {
+    match seq {
+        AstNode::LazySeq(seq) => seq.step(apply),
+        other => {
+            let mut items = Walk::new(&other)?;
+            Ok(items.next(apply)?.map(|first| (first, items.into_seq())))
+        }
+    }
+}
+
+// Dropping a long realized sequence would otherwise recurse once for every
+// item, so the links are taken apart one at a time instead.
+impl Drop for LazySeq {
+    fn drop(&mut self) {
+        let mut parts = vec![];
+        self.take_parts(&mut parts);
+        if !parts.is_empty() {
+            crate::stack::dismantle(parts);
+        }
+    }
+}
+
+// Only the same lazy sequence is equal to itself, since comparing items
+// would mean realizing them.
+impl PartialEq for LazySeq {
+    fn eq(&self, other: &LazySeq) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl fmt::Debug for LazySeq {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LazySeq({})", self)
+    }
+}
+
+/// Prints the items realized so far, and `...` for the rest.
+impl fmt::Display for LazySeq {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut items = vec![];
+        let mut seq = self.clone();
+        loop {
+            let rest = match &*seq.state() {
+                State::Cons(first, rest) => {
+                    items.push(first.to_string());
+                    rest.clone()
+                }
+                State::Empty => break,
+                _ => {
+                    items.push("...".to_string());
+                    break;
+                }
+            };
+            match rest {
+                AstNode::LazySeq(rest) => seq = rest,
+                Terms(rest) => {
+                    items.extend(rest.iter().map(AstNode::to_string));
+                    break;
+                }
+                other => {
+                    items.push(other.to_string());
+                    break;
+                }
+            }
+        }
+        write!(f, "[{}]", items.join(", "))
+    }
+}
+
+/// Makes the next item of a generated sequence from the state so far, and
+/// returns it with the state to carry on from, or `None` at the end.
+pub(crate) type Generator<S> = fn(S, &mut Apply) -> Result<Option<(AstNode, S)>, EvalError>;
+
+/// A lazy sequence whose items are made one at a time by `next`.
+pub(crate) fn generate<S>(state: S, next: Generator<S>) -> AstNode
+where
+    S: Send + 'static,
+{
+    AstNode::LazySeq(LazySeq::new(move |apply| match next(state, apply)? {
+        Some((item, state)) => Ok(AstNode::LazySeq(LazySeq::cons(item, generate(state, next)))),
+        None => Ok(Terms(vec![])),
+    }))
+}
+
+/// Steps through any sequence, realizing lazy ones an item at a time.
+pub(crate) enum Walk {
+    Items(vec::IntoIter<AstNode>),
+    Lazy(LazySeq),
+}
+
+impl Walk {
+    /// Lists, vectors, arrays (row by row), strings (as one-character
+    /// strings) and lazy sequences can all be walked.
+    pub(crate) fn new(seq: &AstNode) -> Result<Walk, EvalError> {
+        let items = match seq {
+            AstNode::LazySeq(seq) => return Ok(Walk::Lazy(seq.clone())),
+            Terms(items) => items.clone(),
+            Value(Primitive::Vector(items)) => items.iter().cloned().map(Value).collect(),
+            Value(Primitive::Array(array)) => array.rows().into_iter().map(Value).collect(),
+            Value(Primitive::Str(s)) => s.chars().map(|c| Value(Primitive::Str(c.to_string()))).collect(),
+            other => return Err(mismatch("a sequence", other)),
+        };
+        Ok(Walk::Items(items.into_iter()))
+    }
+
+    pub(crate) fn next(&mut self, apply: &mut Apply) -> Result<Option<AstNode>, EvalError> {
+        match self {
+            Walk::Items(items) => Ok(items.next()),
+            Walk::Lazy(seq) => match seq.step(apply)? {
+                Some((first, rest)) => {
+                    *self = Walk::new(&rest)?;
+                    Ok(Some(first))
+                }
+                None => {
+                    *self = Walk::Items(vec![].into_iter());
+                    Ok(None)
+                }
+            },
+        }
+    }
+
+    /// Every remaining item. Never returns for an infinite sequence, short
+    /// of an interrupt.
+    pub(crate) fn collect(mut self, apply: &mut Apply) -> Result<Vec<AstNode>, EvalError> {
+        let mut items = vec![];
+        while let Some(item) = self.next(apply)? {
+            interrupt::check()?;
+            items.push(item);
+        }
+        Ok(items)
+    }
+
+    /// What is left, as a sequence.
+    pub(crate) fn into_seq(self) -> AstNode {
+        match self {
+            Walk::Items(items) => Terms(items.collect()),
+            Walk::Lazy(seq) => AstNode::LazySeq(seq),
+        }
+    }
+}
+
+pub(crate) fn contains_lazy(node: &AstNode) -> bool {
+    match node {
+        AstNode::LazySeq(_) => true,
+        Terms(items) => items.iter().any(contains_lazy),
+        _ => false,
+    }
+}
+
+/// Replaces every lazy sequence in `node`, including ones nested in lists,
+/// with a list of its items.
+pub(crate) fn realize(node: AstNode, apply: &mut Apply) -> Result<AstNode, EvalError> {
+    match node {
+        AstNode::LazySeq(_) => realize(Terms(Walk::new(&node)?.collect(apply)?), apply),
+        Terms(items) => Ok(Terms(items.into_iter().map(|item| realize(item, apply)).collect::<Result<_, _>>()?)),
+        other => Ok(other),
+    }
+}
+
+/// Computes up to `limit` items of every lazy sequence in `node`, including
+/// ones among its items, so that printing it shows them.
+pub(crate) fn force(node: &AstNode, limit: usize, apply: &mut Apply) -> Result<(), EvalError> {
+    match node {
+        AstNode::LazySeq(seq) => {
+            let mut items = Walk::Lazy(seq.clone());
+            for _ in 0..limit {
+                interrupt::check()?;
+                match items.next(apply)? {
+                    Some(item) => force(&item, limit, apply)?,
+                    None => break,
+                }
+            }
+            Ok(())
+        }
+        Terms(items) => items.iter().try_for_each(|item| force(item, limit, apply)),
+        _ => Ok(()),
+    }
+}
@@ -3,49 +3,175 @@ extern crate pest;
 extern crate pest_derive;
 
 use self::AstNode::*;
-use pest::error::Error;
-use pest::Parser;
+use pest::error::{Error, ErrorVariant};
+use pest::{Parser, Position};
 use std::collections::HashMap;
-use std::ops::Deref;
+use std::sync::Arc;
 
 #[derive(Parser)]
 #[grammar = "lisp.pest"]
 pub struct LISPParser;
 
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub enum MonadicVerb {
-    GreaterThan,
-    GreaterThanEqual,
-    Multiplication,
-    Difference,
-    Addition,
-    Division,
-    Tally,
-    LessThan,
-    LessThanEqual,
-    Equivalency,
-    NotEquivalent,
-    Power,
-    Or,
-    And,
-}
-
-use operators::Primitive;
+pub use num_bigint::BigInt;
+pub use num_rational::BigRational;
+pub use operators::{OperationError, Primitive};
 mod operators;
 
+pub use broadcast::Broadcast;
+mod broadcast;
+
+pub use error::EvalError;
+mod error;
+
+pub use macros::{expand, gensym};
+mod macros;
+
+pub use stack::{max_depth, set_max_depth, DEFAULT_MAX_DEPTH};
+use stack::EvalFrame;
+mod stack;
+
+pub use interrupt::interrupt;
+mod interrupt;
+
+pub use interpreter::Interpreter;
+mod interpreter;
+
+pub use native::{NativeFn, NativeFnBody};
+mod native;
+
+mod builtins;
+
+mod strings;
+
+mod sequences;
+
+mod indexing;
+
+pub use lazy::LazySeq;
+mod lazy;
+
+pub use arrays::Array;
+mod arrays;
+
+pub use pattern::Pattern;
+mod pattern;
+
+/// A parsed form, and also what a form evaluates to, since Sauce code is
+/// data. Evaluated results are `Value`s, `Terms` (lists), `Ident`s (symbols
+/// from quoted code), `Function`s, `NativeFn`s, `Macro`s and `LazySeq`s.
+///
+/// This is the value type of the embedding API. Variants may be added, so
+/// matches outside this crate need a wildcard arm.
 #[derive(PartialEq, Debug, Clone)]
+#[non_exhaustive]
 pub enum AstNode {
     Value(Primitive),
-    MonadicOp {
-        operator: MonadicVerb,
-        expr: Box<AstNode>,
-    },
     Terms(Vec<AstNode>),
     IsGlobal {
         ident: String,
         expr: Box<AstNode>,
     },
     Ident(String),
+    Quote(Box<AstNode>),
+    Quasiquote(Box<AstNode>),
+    Unquote(Box<AstNode>),
+    UnquoteSplicing(Box<AstNode>),
+    Vector(Vec<AstNode>),
+    Macro(Lambda),
+    Function {
+        lambda: Lambda,
+        closure: Scope,
+    },
+    NativeFn(NativeFn),
+    LazySeq(LazySeq),
+}
+
+/// Local bindings visible to the form being evaluated: function parameters,
+/// `let` and `loop` bindings, and whatever a closure captured.
+pub type Scope = Arc<Bindings>;
+
+/// The names bound in a `Scope`.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Bindings(HashMap<String, AstNode>);
+
+impl std::ops::Deref for Bindings {
+    type Target = HashMap<String, AstNode>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for Bindings {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl FromIterator<(String, AstNode)> for Bindings {
+    fn from_iter<I: IntoIterator<Item = (String, AstNode)>>(bindings: I) -> Self {
+        Bindings(bindings.into_iter().collect())
+    }
+}
+
+// A closure holds the scope it was made in, which may hold the closure made
+// before it, and so on, so a long chain of them is taken apart iteratively.
+impl Drop for Bindings {
+    fn drop(&mut self) {
+        if self.0.values().any(|value| matches!(value, Function { .. } | LazySeq(_) | Terms(_) | Vector(_))) {
+            stack::dismantle(self.0.drain().map(|(_, value)| value).collect());
+        }
+    }
+}
+
+/// Parameters and body shared by anything that can be applied to
+/// arguments. `rest` collects any arguments after `&` in the parameter
+/// vector. The body is shared, so functions are cheap to copy.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Lambda {
+    pub name: String,
+    pub params: Vec<String>,
+    pub rest: Option<String>,
+    pub body: Arc<[AstNode]>,
+}
+
+impl Lambda {
+    /// Builds a lambda from a `[a b & more]` parameter vector and body forms.
+    pub(crate) fn new(name: String, params: &AstNode, body: Vec<AstNode>) -> Result<Lambda, EvalError> {
+        let items = match params {
+            Vector(items) => items,
+            other => return Err(EvalError::InvalidForm(format!("{} expects a parameter vector, got {}", name, other))),
+        };
+        let mut names = vec![];
+        let mut rest = None;
+        let mut iter = items.iter();
+        while let Some(item) = iter.next() {
+            match (item, rest.is_some()) {
+                (Ident(param), false) if param == "&" => match (iter.next(), iter.next()) {
+                    (Some(Ident(param)), None) => rest = Some(param.clone()),
+                    _ => return Err(EvalError::InvalidForm(format!("{} expects exactly one parameter after &", name))),
+                },
+                (Ident(param), _) => names.push(param.clone()),
+                (other, _) => return Err(EvalError::InvalidForm(format!("{} parameters must be symbols, got {}", name, other))),
+            }
+        }
+        Ok(Lambda { name, params: names, rest, body: body.into() })
+    }
+
+    /// Pairs each parameter with its argument, gathering extra arguments into
+    /// the rest parameter.
+    pub(crate) fn bind(&self, mut args: Vec<AstNode>) -> Result<Vec<(String, AstNode)>, EvalError> {
+        if args.len() < self.params.len() || (self.rest.is_none() && args.len() > self.params.len()) {
+            let max = if self.rest.is_some() { usize::MAX } else { self.params.len() };
+            return Err(EvalError::Arity { name: self.name.clone(), min: self.params.len(), max, given: args.len() });
+        }
+        let extra = args.split_off(self.params.len());
+        let mut bindings: Vec<(String, AstNode)> = self.params.iter().cloned().zip(args).collect();
+        if let Some(rest) = &self.rest {
+            bindings.push((rest.clone(), Terms(extra)));
+        }
+        Ok(bindings)
+    }
 }
 
 impl std::fmt::Display for AstNode {
@@ -64,50 +190,204 @@ impl std::fmt::Display for AstNode {
                 },
             IsGlobal { ident, expr } => write!(f, "{}:={}", ident,expr),
             Ident(item) => write!(f, "{}", item),
-            other => write!(f, "{}", other),
+            Quote(item) => write!(f, "'{}", item),
+            Quasiquote(item) => write!(f, "`{}", item),
+            Unquote(item) => write!(f, "~{}", item),
+            UnquoteSplicing(item) => write!(f, "~@{}", item),
+            Vector(items) => {
+                write!(f, "[")?;
+                for (i, e) in items.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", e)?;
+                }
+                write!(f, "]")
+            }
+            Macro(lambda) => write!(f, "#<macro {}>", lambda.name),
+            Function { lambda, .. } => write!(f, "#<fn {}>", lambda.name),
+            NativeFn(native) => write!(f, "#<native-fn {}>", native.name),
+            LazySeq(seq) => write!(f, "{}", seq),
         }
 
     }
 }
 
 
+/// How deeply brackets and quotes may nest in source code. Reading forms,
+/// and everything that walks them afterwards, recurses once per level.
+const MAX_NESTING: usize = 1_000;
+
+/// The native stack the parser needs for each level of nesting, with room
+/// to spare in debug builds.
+const PARSE_STACK_PER_LEVEL: usize = 16 * 1024;
+
+#[allow(clippy::result_large_err)]
 pub fn parse(source: &str) -> Result<Vec<AstNode>, Error<Rule>> {
+    let depth = nesting(source).map_err(|at| {
+        let message = format!("Forms can't be nested more than {} levels deep", MAX_NESTING);
+        let position = Position::new(source, at).expect("nesting stops at an ASCII character");
+        Error::new_from_pos(ErrorVariant::CustomError { message }, position)
+    })?;
+    // The parser recurses once per level, so deeply nested source is read
+    // on a stack of its own rather than risk running out.
+    let needed = stack::RED_ZONE + depth * PARSE_STACK_PER_LEVEL;
+    stacker::maybe_grow(needed, needed, || parse_forms(source))
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_forms(source: &str) -> Result<Vec<AstNode>, Error<Rule>> {
     let mut ast = vec![];
 
     let pairs = LISPParser::parse(Rule::lisp, source)?;
+    // The grammar checks the shape of escapes and regexes, but not that
+    // `\u{...}` names a real character or that a regex compiles.
+    for pair in pairs.clone().flatten() {
+        let s = pair.as_str();
+        let checked = match pair.as_rule() {
+            Rule::doubleString => unescape(&s[1..s.len() - 1]).map(|_| ()),
+            Rule::regex => Pattern::new(&s[2..s.len() - 1]).map(|_| ()).map_err(|err| err.to_string()),
+            _ => Ok(()),
+        };
+        if let Err(message) = checked {
+            return Err(Error::new_from_span(ErrorVariant::CustomError { message }, pair.as_span()));
+        }
+    }
     for pair in pairs {
-        match pair.as_rule() {
-            Rule::expr => {
-                ast.push(build_ast_from_expr(pair));
-            }
-            _ => {}
+        if pair.as_rule() == Rule::expr {
+            ast.push(build_ast_from_expr(pair));
         }
     }
 
     Ok(ast)
 }
 
+/// How deeply brackets and quotes nest in `source`, or the offset where
+/// they first nest deeper than `MAX_NESTING`. Strings, regexes and comments
+/// are skipped, and a quote adds a level to the form it quotes.
+fn nesting(source: &str) -> Result<usize, usize> {
+    let bytes = source.as_bytes();
+    // The levels each open bracket added: its own and those of its quotes.
+    let mut open = vec![];
+    let mut depth = 0;
+    let mut quotes = 0;
+    let mut deepest = 0;
+    let mut at = 0;
+    while at < bytes.len() {
+        let start = at;
+        at += 1;
+        match bytes[start] {
+            b'(' | b'[' => {
+                open.push(1 + quotes);
+                depth += 1 + quotes;
+                quotes = 0;
+            }
+            b')' | b']' => depth -= open.pop().unwrap_or(0),
+            b'\'' => match single_quoted_end(bytes, start) {
+                Some(end) => {
+                    quotes = 0;
+                    at = end;
+                }
+                None => quotes += 1,
+            },
+            b'`' => quotes += 1,
+            b'~' => {
+                quotes += 1;
+                at += (bytes.get(at) == Some(&b'@')) as usize;
+            }
+            b'"' => at = quoted_end(bytes, at),
+            b';' => at = bytes[at..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |end| at + end),
+            b'#' => match bytes.get(at) {
+                Some(b'_') => {
+                    quotes += 1;
+                    at += 1;
+                }
+                Some(b'|') => at = block_comment_end(bytes, at + 1),
+                Some(b'"') => at = quoted_end(bytes, at + 1),
+                _ => quotes = 0,
+            },
+            b' ' | b'\t' | b'\r' | b'\n' => {}
+            _ => quotes = 0,
+        }
+        let level = depth + quotes;
+        if level > MAX_NESTING {
+            return Err(start);
+        }
+        deepest = deepest.max(level);
+    }
+    Ok(deepest)
+}
+
+/// Where a single-quoted string starting at `start` ends, if it is one
+/// rather than a quote. As in the grammar, the closing quote must end the
+/// token.
+fn single_quoted_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut at = start + 1;
+    while at < bytes.len() {
+        if bytes[at] != b'\'' {
+            at += 1;
+        } else if bytes.get(at + 1) == Some(&b'\'') {
+            at += 2;
+        } else {
+            let rest = &bytes[at + 1..];
+            let ends = matches!(rest.first(), None | Some(b' ' | b'\t' | b'\r' | b'\n' | b')' | b']' | b';'))
+                || rest.starts_with(b"#|")
+                || rest.starts_with(b"#_");
+            return ends.then_some(at + 1);
+        }
+    }
+    None
+}
+
+/// The end of a double-quoted string or regex whose body starts at `at`.
+fn quoted_end(bytes: &[u8], mut at: usize) -> usize {
+    while at < bytes.len() {
+        match bytes[at] {
+            b'\\' => at += 2,
+            b'"' => return at + 1,
+            _ => at += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// The end of a block comment whose body starts at `at`. They nest.
+fn block_comment_end(bytes: &[u8], mut at: usize) -> usize {
+    let mut depth = 1;
+    while at < bytes.len() && depth > 0 {
+        if bytes[at..].starts_with(b"#|") {
+            depth += 1;
+            at += 2;
+        } else if bytes[at..].starts_with(b"|#") {
+            depth -= 1;
+            at += 2;
+        } else {
+            at += 1;
+        }
+    }
+    at.min(bytes.len())
+}
+
+/// The children of `pair`, leaving out the comments the grammar keeps for
+/// tools that need their spans.
+fn children(pair: pest::iterators::Pair<Rule>) -> impl Iterator<Item = pest::iterators::Pair<Rule>> {
+    pair.into_inner().filter(|pair| pair.as_rule() != Rule::COMMENT)
+}
+
 fn build_ast_from_expr(pair: pest::iterators::Pair<Rule>) -> AstNode {
     match pair.as_rule() {
-        Rule::expr => build_ast_from_expr(pair.into_inner().next().unwrap()),
-        Rule::monadicExpr => {
-            let mut pair = pair.into_inner();
-            let operator = pair.next().unwrap();
-            let expr = pair.next().unwrap();
-            let expr = build_ast_from_expr(expr);
-            parse_monadic_verb(operator, expr)
-        }
+        Rule::expr => build_ast_from_expr(children(pair).next().unwrap()),
         Rule::terms => {
-            let terms: Vec<AstNode> = pair.into_inner().map(build_ast_from_term).collect();
+            let terms: Vec<AstNode> = children(pair).map(build_ast_from_term).collect();
             // If there's just a single term, return it without
             // wrapping it in a Terms node.
             match terms.len() {
-                1 => terms.get(0).unwrap().clone(),
+                1 => terms.first().unwrap().clone(),
                 _ => Terms(terms),
             }
         }
         Rule::assgmtExpr => {
-            let mut pair = pair.into_inner();
+            let mut pair = children(pair);
             let ident = pair.next().unwrap();
             let expr = pair.next().unwrap();
             let expr = build_ast_from_expr(expr);
@@ -121,59 +401,49 @@ fn build_ast_from_expr(pair: pest::iterators::Pair<Rule>) -> AstNode {
 }
 
 
-fn parse_monadic_verb(pair: pest::iterators::Pair<Rule>, expr: AstNode) -> AstNode {
-    AstNode::MonadicOp {
-        operator: match pair.as_str() {
-            ">" => MonadicVerb::GreaterThan,
-            ">=" => MonadicVerb::GreaterThanEqual,
-            "*" => MonadicVerb::Multiplication,
-            "-" => MonadicVerb::Difference,
-            "+" => MonadicVerb::Addition,
-            "/" => MonadicVerb::Division,
-            "#" => MonadicVerb::Tally,
-            "<" => MonadicVerb::LessThan,
-            "<=" => MonadicVerb::LessThan,
-            "==" => MonadicVerb::Equivalency,
-            "!=" => MonadicVerb::NotEquivalent,
-            "^" => MonadicVerb::Power,
-            "|" =>MonadicVerb::Or,
-            "&" =>MonadicVerb::And,
-            _ => panic!("Unsupported monadic operator: {}", pair.as_str()),
-        },
-        expr: Box::new(expr),
-    }
-}
-
 fn build_ast_from_term(pair: pest::iterators::Pair<Rule>) -> AstNode {
     match pair.as_rule() {
-        Rule::value => build_ast_from_values(pair.into_inner().next().unwrap()),
-        Rule::expr => build_ast_from_expr(pair),
+        Rule::value => build_ast_from_values(children(pair).next().unwrap()),
+        Rule::expr => {
+            // A parenthesised lone symbol is a call with no arguments, so it
+            // must not be unwrapped into a bare symbol like other single terms.
+            let inner = children(pair.clone()).next().unwrap();
+            match build_ast_from_expr(pair) {
+                Ident(name) if inner.as_rule() == Rule::terms => Terms(vec![Ident(name)]),
+                node => node,
+            }
+        }
+        Rule::vector => Vector(children(pair).map(build_ast_from_term).collect()),
         Rule::ident => AstNode::Ident(String::from(pair.as_str())),
+        Rule::derivedVerb => {
+            // `+/` is `(insert +)`. The builtin is looked up now so that
+            // shadowing `insert` or `scan` doesn't change what `/` and `\` do.
+            let mut parts = children(pair);
+            let verb = build_ast_from_term(parts.next().unwrap());
+            parts.fold(verb, |verb, adverb| {
+                let name = if adverb.as_str() == "/" { "insert" } else { "scan" };
+                Terms(vec![builtins::lookup(name).unwrap(), verb])
+            })
+        }
+        Rule::quote => Quote(Box::new(build_ast_from_term(children(pair).next().unwrap()))),
+        Rule::quasiquote => Quasiquote(Box::new(build_ast_from_term(children(pair).next().unwrap()))),
+        Rule::unquote => Unquote(Box::new(build_ast_from_term(children(pair).next().unwrap()))),
+        Rule::unquoteSplicing => UnquoteSplicing(Box::new(build_ast_from_term(children(pair).next().unwrap()))),
         unknown_term => panic!("Unexpected term: {:?}", unknown_term),
     }
 }
 
 fn build_ast_from_values(pair: pest::iterators::Pair<Rule>) -> AstNode {
     match pair.as_rule() {
-        Rule::integer => {
-            let istr = pair.as_str();
-            let (sign, istr) = match &istr[..1] {
-                "_" => (-1, &istr[1..]),
-                _ => (1, &istr[..]),
-            };
-            let integer: i32 = istr.parse().unwrap();
-            Value(Primitive::Integer(sign * integer))
-        }
+        Rule::integer => integer_literal(pair.as_str(), "", 10),
+        Rule::hexadecimal => integer_literal(pair.as_str(), "0x", 16),
+        Rule::binary => integer_literal(pair.as_str(), "0b", 2),
         Rule::decimal => {
-            let dstr = pair.as_str();
-            let (sign, dstr) = match &dstr[..1] {
-                "_" => (-1.0, &dstr[1..]),
-                _ => (1.0, &dstr[..]),
-            };
+            let (negative, dstr) = split_sign(pair.as_str());
             let mut flt: f64 = dstr.parse().unwrap();
-            if flt != 0.0 {
-                // Avoid negative zeroes; only multiply sign by nonzeroes.
-                flt *= sign;
+            if negative && flt != 0.0 {
+                // Avoid negative zeroes; only negate nonzeroes.
+                flt = -flt;
             }
             Value(Primitive::DoublePrecisionFloat(flt))
         }
@@ -182,16 +452,70 @@ fn build_ast_from_values(pair: pest::iterators::Pair<Rule>) -> AstNode {
             // Strip leading and ending quotes.
             let s = &s[1..s.len() - 1];
             // Escaped string quotes become single quotes here.
-            println!("this is the value: _{:?}_", pair.as_rule());
             let s = s.replace("''", "'");
             Value(Primitive::Str(s))
         }
+        Rule::doubleString => {
+            let s = pair.as_str();
+            // `parse` has already rejected strings that don't unescape.
+            Value(Primitive::Str(unescape(&s[1..s.len() - 1]).unwrap()))
+        }
+        Rule::regex => {
+            let s = pair.as_str();
+            // `parse` has already rejected regexes that don't compile.
+            Value(Primitive::Regex(Pattern::new(&s[2..s.len() - 1]).unwrap()))
+        }
         unknown_term => {
             panic!("Unexpected term: {:?}", unknown_term)
         },
     }
 }
 
+/// Replaces the escapes in the body of a double-quoted string with the
+/// characters they stand for.
+fn unescape(body: &str) -> Result<String, String> {
+    let mut result = String::new();
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('"') => result.push('"'),
+            Some('\\') => result.push('\\'),
+            Some('u') => {
+                let hex: String = chars.by_ref().skip(1).take_while(|&c| c != '}').collect();
+                let code = u32::from_str_radix(&hex, 16).map_err(|_| format!("Invalid unicode escape \\u{{{}}}", hex))?;
+                let c = char::from_u32(code).ok_or_else(|| format!("\\u{{{}}} is not a unicode character", hex))?;
+                result.push(c);
+            }
+            other => return Err(format!("Unknown escape \\{}", other.map(String::from).unwrap_or_default())),
+        }
+    }
+    Ok(result)
+}
+
+/// Strips a leading `-` or `_`, reporting whether there was one.
+fn split_sign(literal: &str) -> (bool, &str) {
+    match literal.strip_prefix(['-', '_']) {
+        Some(rest) => (true, rest),
+        None => (false, literal),
+    }
+}
+
+/// Reads an integer literal in `radix` after its sign and `prefix` (`0x` or
+/// `0b`, in either case).
+fn integer_literal(literal: &str, prefix: &str, radix: u32) -> AstNode {
+    let (negative, digits) = split_sign(literal);
+    let digits = &digits[prefix.len()..];
+    // Literals too large for 64 bits are read as bignums.
+    let integer = BigInt::parse_bytes(digits.as_bytes(), radix).unwrap();
+    Value(Primitive::from(if negative { -integer } else { integer }))
+}
+
 // fn main() {
 //     // let unparsed_file = std::fs::read_to_string("+ 1 2").expect("cannot read ijs file");
 //     let astnode = parse("+ 5 (* 2 2)").expect("unsuccessful parse");
@@ -206,81 +530,353 @@ fn build_ast_from_values(pair: pest::iterators::Pair<Rule>) -> AstNode {
 
 
 
-pub fn evaluate(ast: Vec<AstNode>, saucy_vars: &mut HashMap<String, AstNode>) -> Result<AstNode, Error<Rule>> {
-    let peel = ast[0].clone();
-    match peel {
-        AstNode::MonadicOp { operator, expr } => {
-            // match base.operator {
-            //     GreaterThan=> ,
-            //     GreaterThanEqual=> ,
-            //     Multiplication=> ,
-            //     Difference=>,
-            //     Addition=>,
-            //     Division=>,
-            //     Tally=>,
-            //     LessThan=>,
-            //     LessThanEqual=>,
-            //     Equivalency=>,
-            //     NotEquivalent=>,
-            //     Power=>,
-            //     Or=>,
-            //     And=>,
-            // }
-            println!("1 The first thing is a function with operator: {:#?}", operator);
-
-            println!("1 The first thing is a function and parameters: {:?}", expr);
-            let mut expV  = vec![];
-            expV.push(*expr);
-            let ans = evaluate(expV, saucy_vars);
-            println!("1 The first thing is assignment and value: {:?}", ans.unwrap());
-            Ok(Value(Primitive::Str("todo MonadicOp".to_string())))
-        },
+
+
+
+pub fn evaluate(ast: Vec<AstNode>, saucy_vars: &mut HashMap<String, AstNode>) -> Result<AstNode, EvalError> {
+    interrupt::reset();
+    let mut result = Terms(vec![]);
+    // Lazy sequences are returned unrealized, since they may well be
+    // infinite. `Interpreter::force` computes what is to be printed.
+    for node in ast {
+        result = evaluate_top_level(node, saucy_vars)?;
+    }
+    Ok(result)
+}
+
+pub(crate) fn evaluate_top_level(node: AstNode, saucy_vars: &mut HashMap<String, AstNode>) -> Result<AstNode, EvalError> {
+    evaluate_node(node, &Scope::default(), saucy_vars)
+}
+
+/// What a special form asks the evaluator loop to do next. Anything other
+/// than `Done` is in tail position and is carried out without recursing.
+enum Tail {
+    Done(AstNode),
+    Continue(AstNode, Scope),
+    Enter(Lambda, Scope, Vec<AstNode>),
+    Recur(Vec<AstNode>),
+}
+
+pub(crate) fn evaluate_node(node: AstNode, scope: &Scope, saucy_vars: &mut HashMap<String, AstNode>) -> Result<AstNode, EvalError> {
+    run(Tail::Continue(node, Arc::clone(scope)), saucy_vars)
+}
+
+/// Calls a function with arguments that are already evaluated. This is how
+/// builtins such as `map` call the functions they are given.
+pub(crate) fn apply(func: AstNode, args: Vec<AstNode>, saucy_vars: &mut HashMap<String, AstNode>) -> Result<AstNode, EvalError> {
+    match func {
+        Function { .. } | NativeFn(_) => run(call(func, args, saucy_vars)?, saucy_vars),
+        other => Err(EvalError::InvalidForm(format!("{} is not a function", other))),
+    }
+}
+
+fn run(tail: Tail, saucy_vars: &mut HashMap<String, AstNode>) -> Result<AstNode, EvalError> {
+    let frame = EvalFrame::enter()?;
+    // Tail positions hand back the next form rather than recursing, so tail
+    // calls and `recur` run in constant native stack.
+    let mut tail = tail;
+    // The innermost `loop` or function body, and the scope its body runs
+    // in, for `recur` to jump back to.
+    let mut recur_point: Option<(Lambda, Scope)> = None;
+    loop {
+        let (node, scope) = match tail {
+            Tail::Done(value) => return Ok(value),
+            Tail::Continue(next, next_scope) => (next, next_scope),
+            Tail::Enter(lambda, base, args) => {
+                frame.name(&lambda.name);
+                let scope = extend_scope(&base, lambda.bind(args)?);
+                let node = body_form(&lambda.body);
+                recur_point = Some((lambda, Arc::clone(&scope)));
+                (node, scope)
+            }
+            Tail::Recur(args) => {
+                let (lambda, scope) = recur_point
+                    .as_mut()
+                    .ok_or_else(|| EvalError::InvalidForm("Can only recur from tail position".to_string()))?;
+                // The last iteration's scope is usually only held here by
+                // now, so the new values replace the old ones in place. A
+                // closure that captured it keeps its own copy.
+                Arc::make_mut(scope).extend(lambda.bind(args)?);
+                (body_form(&lambda.body), Arc::clone(scope))
+            }
+        };
+        interrupt::check()?;
+        tail = match node {
+            Terms(t) => match t.first() {
+                Some(Ident(head)) if is_special_form(head, &scope, saucy_vars) => {
+                    evaluate_special_form(t, &scope, saucy_vars)?
+                }
+                _ => match evaluate_terms(t, &scope, saucy_vars)? {
+                    Terms(mut values) if matches!(values.first(), Some(Function { .. } | NativeFn(_))) => {
+                        let args = values.split_off(1);
+                        call(values.remove(0), args, saucy_vars)?
+                    }
+                    values => Tail::Done(stack::check_nesting(values)?),
+                },
+            },
+            other => Tail::Done(evaluate_form(other, &scope, saucy_vars)?),
+        };
+    }
+}
+
+/// Native functions are called straight away. Sauce functions are entered
+/// by the evaluator loop, so that calls in tail position don't grow the
+/// stack.
+fn call(func: AstNode, args: Vec<AstNode>, saucy_vars: &mut HashMap<String, AstNode>) -> Result<Tail, EvalError> {
+    match func {
+        Function { lambda, closure } => {
+            let mut closure = closure;
+            // A named function can always call itself.
+            if lambda.name != "fn" {
+                let itself = Function { lambda: lambda.clone(), closure: Arc::clone(&closure) };
+                Arc::make_mut(&mut closure).insert(lambda.name.clone(), itself);
+            }
+            Ok(Tail::Enter(lambda, closure, args))
+        }
+        NativeFn(native) => {
+            let mut callback = |func: &AstNode, args: Vec<AstNode>| apply(func.clone(), args, saucy_vars);
+            Ok(Tail::Done(stack::check_nesting(native.call_with(&args, &mut callback)?)?))
+        }
+        _ => unreachable!("only called with functions"),
+    }
+}
+
+/// Evaluates everything that isn't a list: values, symbols and the quoting
+/// forms.
+fn evaluate_form(node: AstNode, scope: &Scope, saucy_vars: &mut HashMap<String, AstNode>) -> Result<AstNode, EvalError> {
+    match node {
         AstNode::IsGlobal { ident, expr } => {
-            let value = (*expr).clone();
-            saucy_vars.insert(ident, *expr);
+            let value = evaluate_node(*expr, scope, saucy_vars)?;
+            saucy_vars.insert(ident, value.clone());
             Ok(value)
         },
-        Value(Primitive::Integer(i)) => {
-            Ok(Value(Primitive::Integer(i)))
-        },
-        Value(Primitive::DoublePrecisionFloat(f)) => Ok(Value(Primitive::DoublePrecisionFloat(f))),
-        Terms(t) => {
-            Ok(evaluate_terms(t,saucy_vars)?)
-        },
+        Value(value) => Ok(Value(value)),
+        Terms(t) => evaluate_node(Terms(t), scope, saucy_vars),
         Ident(v) => {
-            let v = saucy_vars.get(&v).unwrap();
-            Ok(v.clone())
+            // Builtins come last so that programs can shadow them.
+            match scope.get(&v).or_else(|| saucy_vars.get(&v)) {
+                Some(value) => Ok(value.clone()),
+                None => builtins::lookup(&v).ok_or(EvalError::UnboundSymbol(v)),
+            }
+        },
+        Quote(form) => Ok(*form),
+        Quasiquote(form) => stack::check_nesting(quasiquote(*form, scope, saucy_vars)?),
+        Unquote(_) | UnquoteSplicing(_) => Err(EvalError::UnquoteOutsideQuasiquote),
+        Vector(items) => stack::check_nesting(evaluate_terms(items, scope, saucy_vars)?),
+        Macro(lambda) => Ok(Macro(lambda)),
+        Function { lambda, closure } => Ok(Function { lambda, closure }),
+        NativeFn(native) => Ok(NativeFn(native)),
+        LazySeq(seq) => Ok(LazySeq(seq)),
+    }
+}
+
+/// Lists are false when empty, values follow `Primitive::is_truthy` and
+/// everything else is true.
+fn is_truthy(node: &AstNode) -> bool {
+    match node {
+        Value(value) => value.is_truthy(),
+        Terms(items) => !items.is_empty(),
+        _ => true,
+    }
+}
+
+fn extend_scope(scope: &Scope, bindings: Vec<(String, AstNode)>) -> Scope {
+    let mut scope = Arc::clone(scope);
+    Arc::make_mut(&mut scope).extend(bindings);
+    scope
+}
+
+/// Wraps a multi-form body in a `do` so it can be evaluated as one form.
+fn body_form(body: &[AstNode]) -> AstNode {
+    match body {
+        [form] => form.clone(),
+        _ => Terms([Ident("do".to_string())].into_iter().chain(body.iter().cloned()).collect()),
+    }
+}
+
+fn is_special_form(head: &str, scope: &Scope, saucy_vars: &HashMap<String, AstNode>) -> bool {
+    if scope.contains_key(head) {
+        return false;
+    }
+    matches!(
+        head,
+        "if" | "do" | "let" | "fn" | "defn" | "loop" | "recur" | "defmacro" | "macroexpand" | "macroexpand-1"
+            | "gensym" | "lazy-seq"
+    ) || matches!(saucy_vars.get(head), Some(Macro(_)))
+}
+
+fn evaluate_special_form(form: Vec<AstNode>, scope: &Scope, saucy_vars: &mut HashMap<String, AstNode>) -> Result<Tail, EvalError> {
+    let head = match form.first() {
+        Some(Ident(head)) => head.clone(),
+        _ => unreachable!("special forms start with a symbol"),
+    };
+    match head.as_str() {
+        "if" => {
+            if form.len() != 3 && form.len() != 4 {
+                return Err(EvalError::Arity { name: head, min: 2, max: 3, given: form.len() - 1 });
+            }
+            let mut form = form.into_iter().skip(1);
+            let test = evaluate_node(form.next().unwrap(), scope, saucy_vars)?;
+            let then = form.next().unwrap();
+            let otherwise = form.next().unwrap_or(Terms(vec![]));
+            let branch = if is_truthy(&test) { then } else { otherwise };
+            Ok(Tail::Continue(branch, Arc::clone(scope)))
+        }
+        "do" => {
+            let mut body: Vec<AstNode> = form.into_iter().skip(1).collect();
+            let last = body.pop().unwrap_or(Terms(vec![]));
+            for node in body {
+                evaluate_node(node, scope, saucy_vars)?;
+            }
+            Ok(Tail::Continue(last, Arc::clone(scope)))
+        }
+        "let" => {
+            let mut form = form.into_iter().skip(1);
+            let mut local = Arc::clone(scope);
+            for (name, init) in binding_pairs(&head, form.next())? {
+                let value = evaluate_node(init, &local, saucy_vars)?;
+                local = extend_scope(&local, vec![(name, value)]);
+            }
+            Ok(Tail::Continue(body_form(&form.collect::<Vec<_>>()), local))
+        }
+        "loop" => {
+            let mut form = form.into_iter().skip(1);
+            let mut local = Arc::clone(scope);
+            let mut params = vec![];
+            let mut values = vec![];
+            for (name, init) in binding_pairs(&head, form.next())? {
+                let value = evaluate_node(init, &local, saucy_vars)?;
+                local = extend_scope(&local, vec![(name.clone(), value.clone())]);
+                params.push(name);
+                values.push(value);
+            }
+            let lambda = Lambda { name: head, params, rest: None, body: form.collect() };
+            Ok(Tail::Enter(lambda, Arc::clone(scope), values))
+        }
+        "recur" => {
+            let mut args = vec![];
+            for arg in form.into_iter().skip(1) {
+                args.push(evaluate_node(arg, scope, saucy_vars)?);
+            }
+            Ok(Tail::Recur(args))
+        }
+        "fn" => {
+            let mut form = form.into_iter().skip(1).peekable();
+            let name = match form.peek() {
+                Some(Ident(name)) => name.clone(),
+                _ => head,
+            };
+            if name != "fn" {
+                form.next();
+            }
+            let params = form.next().unwrap_or(Terms(vec![]));
+            let lambda = Lambda::new(name, &params, form.collect())?;
+            Ok(Tail::Done(Function { lambda, closure: Arc::clone(scope) }))
+        }
+        "defn" => {
+            let mut form = form.into_iter().skip(1);
+            let name = match form.next() {
+                Some(Ident(name)) => name,
+                _ => return Err(EvalError::InvalidForm("defn expects a symbol name".to_string())),
+            };
+            let params = form.next().unwrap_or(Terms(vec![]));
+            let lambda = Lambda::new(name.clone(), &params, form.collect())?;
+            saucy_vars.insert(name.clone(), Function { lambda, closure: Arc::clone(scope) });
+            Ok(Tail::Done(Ident(name)))
+        }
+        "lazy-seq" => {
+            // The body becomes a function of no arguments, called the first
+            // time anything looks inside the sequence.
+            let lambda = Lambda { name: "fn".to_string(), params: vec![], rest: None, body: form[1..].into() };
+            let body = Function { lambda, closure: Arc::clone(scope) };
+            Ok(Tail::Done(LazySeq(lazy::LazySeq::delay(body))))
+        }
+        "defmacro" => Ok(Tail::Done(macros::define_macro(form, saucy_vars)?)),
+        "macroexpand" | "macroexpand-1" => {
+            if form.len() != 2 {
+                return Err(EvalError::Arity { name: head, min: 1, max: 1, given: form.len() - 1 });
+            }
+            let arg = evaluate_node(form[1].clone(), scope, saucy_vars)?;
+            if head == "macroexpand" {
+                Ok(Tail::Done(macros::macroexpand(arg, saucy_vars)?))
+            } else {
+                Ok(Tail::Done(macros::macroexpand_1(arg, saucy_vars)?.0))
+            }
+        }
+        "gensym" => match form.len() {
+            1 => Ok(Tail::Done(gensym("G"))),
+            2 => match evaluate_node(form[1].clone(), scope, saucy_vars)? {
+                Ident(prefix) | Value(Primitive::Str(prefix)) => Ok(Tail::Done(gensym(&prefix))),
+                other => Err(EvalError::InvalidForm(format!("gensym expects a symbol or string prefix, got {}", other))),
+            },
+            given => Err(EvalError::Arity { name: head, min: 0, max: 1, given: given - 1 }),
         },
-        Value(Primitive::Str(s)) => Ok(Value(Primitive::Str(s))),
-        Value(Primitive::Vector(_)) => Ok(Value(Primitive::Str("todo Vector".to_string()))),
+        // Macros are normally expanded before evaluation, but a form built at
+        // runtime can still reach the evaluator with a macro call in it.
+        _ => Ok(Tail::Continue(macros::macroexpand(Terms(form), saucy_vars)?, Arc::clone(scope))),
+    }
+}
+
+/// Reads the `[name init name init ...]` vector of a `let` or `loop`.
+fn binding_pairs(head: &str, bindings: Option<AstNode>) -> Result<Vec<(String, AstNode)>, EvalError> {
+    let items = match bindings {
+        Some(Vector(items)) if items.len() % 2 == 0 => items,
+        _ => return Err(EvalError::InvalidForm(format!("{} expects a vector with an even number of forms", head))),
+    };
+    let mut pairs = vec![];
+    let mut items = items.into_iter();
+    while let (Some(name), Some(init)) = (items.next(), items.next()) {
+        match name {
+            Ident(name) => pairs.push((name, init)),
+            other => return Err(EvalError::InvalidForm(format!("{} binding names must be symbols, got {}", head, other))),
+        }
     }
- 
+    Ok(pairs)
 }
 
-fn evaluate_terms(ast: Vec<AstNode>, saucy_vars: &mut HashMap<String, AstNode>) -> Result<AstNode, Error<Rule>> {
+fn evaluate_terms(ast: Vec<AstNode>, scope: &Scope, saucy_vars: &mut HashMap<String, AstNode>) -> Result<AstNode, EvalError> {
     let mut v: Vec<AstNode> = vec![];
     for node in ast {
         match node {
             Value(_) => v.push(node),
-            MonadicOp { operator, expr } => {
+            other => v.push(evaluate_node(other, scope, saucy_vars)?),
+        }
+    }
+    Ok(Terms(v))
+}
 
+/// Walks a quasiquoted form, evaluating `~x` in place and splicing the
+/// elements of `~@xs` into the enclosing list. Everything else is returned
+/// as data.
+fn quasiquote(form: AstNode, scope: &Scope, saucy_vars: &mut HashMap<String, AstNode>) -> Result<AstNode, EvalError> {
+    let _frame = EvalFrame::enter()?;
+    match form {
+        Unquote(expr) => evaluate_node(*expr, scope, saucy_vars),
+        UnquoteSplicing(_) => Err(EvalError::SpliceOutsideList),
+        Terms(items) => Ok(Terms(quasiquote_items(items, scope, saucy_vars)?)),
+        IsGlobal { ident, expr } => Ok(IsGlobal {
+            ident,
+            expr: Box::new(quasiquote(*expr, scope, saucy_vars)?),
+        }),
+        Quote(expr) => Ok(Quote(Box::new(quasiquote(*expr, scope, saucy_vars)?))),
+        Vector(items) => Ok(Vector(quasiquote_items(items, scope, saucy_vars)?)),
+        other => Ok(other),
+    }
+}
+
+fn quasiquote_items(items: Vec<AstNode>, scope: &Scope, saucy_vars: &mut HashMap<String, AstNode>) -> Result<Vec<AstNode>, EvalError> {
+    let mut result = vec![];
+    for item in items {
+        match item {
+            UnquoteSplicing(expr) => match evaluate_node(*expr, scope, saucy_vars)? {
+                Terms(spliced) => result.extend(spliced),
+                Value(Primitive::Vector(spliced)) => result.extend(spliced.into_iter().map(Value)),
+                other => return Err(EvalError::NotSpliceable(other)),
             },
-            Terms(v_node) => {
-                let nested_node = evaluate_terms(v_node, saucy_vars)?;
-                v.push(nested_node);
-            },
-            IsGlobal{ ident, expr } => {
-                let value = (*expr).clone();
-                saucy_vars.insert(ident, *expr);
-                v.push(value.clone());
-            },
-            Ident(i) => {
-                let value = saucy_vars.get(&i).unwrap();
-                v.push(value.clone());
-            },
+            other => result.push(quasiquote(other, scope, saucy_vars)?),
         }
     }
-    Ok(Terms(v))
+    Ok(result)
 }
 
 // fn addition<T>(ast: Vec<AstNode>) -> Result<T, Error<Rule>> {
@@ -289,4 +885,4 @@ fn evaluate_terms(ast: Vec<AstNode>, saucy_vars: &mut HashMap<String, AstNode>)
 //         sum.push(item)
 //     }
 //     Ok()
-// }
\ No newline at end of file
+// }
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::AstNode::{self, *};
+use crate::stack::EvalFrame;
+use crate::{evaluate_node, EvalError, Lambda};
+
+static GENSYM_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns a fresh symbol for use in macro templates, so that bindings a
+/// macro introduces can't capture the caller's symbols.
+pub fn gensym(prefix: &str) -> AstNode {
+    let id = GENSYM_COUNTER.fetch_add(1, Ordering::Relaxed);
+    Ident(format!("{}__{}", prefix, id))
+}
+
+/// The macro expansion pass, run between `parse` and `evaluate`.
+///
+/// Top level `defmacro` forms are registered as they are reached, so a macro
+/// can be used by the forms that follow it in the same source. Expanding a
+/// form can call functions, so `Interpreter::eval_str` expands each form
+/// only after evaluating the ones before it.
+pub fn expand(ast: Vec<AstNode>, saucy_vars: &mut HashMap<String, AstNode>) -> Result<Vec<AstNode>, EvalError> {
+    let mut expanded = vec![];
+    for form in ast {
+        if is_form(&form, "defmacro") {
+            let name = define_macro(form_items(form), saucy_vars)?;
+            expanded.push(Quote(Box::new(name)));
+        } else {
+            expanded.push(expand_form(form, saucy_vars)?);
+        }
+    }
+    Ok(expanded)
+}
+
+pub(crate) fn define_macro(form: Vec<AstNode>, saucy_vars: &mut HashMap<String, AstNode>) -> Result<AstNode, EvalError> {
+    let mut form = form.into_iter().skip(1);
+    let name = match form.next() {
+        Some(Ident(name)) => name,
+        _ => return Err(EvalError::InvalidForm("defmacro expects a symbol name".to_string())),
+    };
+    let params = form.next().unwrap_or(Terms(vec![]));
+    let lambda = Lambda::new(name.clone(), &params, form.collect())?;
+    saucy_vars.insert(name.clone(), Macro(lambda));
+    Ok(Ident(name))
+}
+
+/// Expands `form` once if it is a macro call. The flag reports whether an
+/// expansion happened.
+pub(crate) fn macroexpand_1(form: AstNode, saucy_vars: &mut HashMap<String, AstNode>) -> Result<(AstNode, bool), EvalError> {
+    let lambda = match &form {
+        Terms(items) => match items.first() {
+            Some(Ident(head)) => match saucy_vars.get(head) {
+                Some(Macro(lambda)) => lambda.clone(),
+                _ => return Ok((form, false)),
+            },
+            _ => return Ok((form, false)),
+        },
+        _ => return Ok((form, false)),
+    };
+    let args = form_items(form).into_iter().skip(1).collect();
+
+    // Macro parameters are bound to the unevaluated argument forms.
+    let scope = Arc::new(lambda.bind(args)?.into_iter().collect());
+    let mut expansion = Terms(vec![]);
+    for body in lambda.body.iter() {
+        expansion = evaluate_node(body.clone(), &scope, saucy_vars)?;
+    }
+    Ok((expansion, true))
+}
+
+/// Expands `form` until its head is no longer a macro.
+pub(crate) fn macroexpand(form: AstNode, saucy_vars: &mut HashMap<String, AstNode>) -> Result<AstNode, EvalError> {
+    let mut form = form;
+    loop {
+        let (expansion, expanded) = macroexpand_1(form, saucy_vars)?;
+        if !expanded {
+            return Ok(expansion);
+        }
+        form = expansion;
+    }
+}
+
+/// Fully expands `form` and every subform that is evaluated as code.
+fn expand_form(form: AstNode, saucy_vars: &mut HashMap<String, AstNode>) -> Result<AstNode, EvalError> {
+    // Expansions can nest deeper than anything the parser returns.
+    let _frame = EvalFrame::enter()?;
+    match macroexpand(form, saucy_vars)? {
+        // Macro bodies are expanded when the macro is called, not defined.
+        form if is_form(&form, "defmacro") => Ok(form),
+        Terms(items) => Ok(Terms(expand_all(items, saucy_vars)?)),
+        Vector(items) => Ok(Vector(expand_all(items, saucy_vars)?)),
+        IsGlobal { ident, expr } => Ok(IsGlobal { ident, expr: Box::new(expand_form(*expr, saucy_vars)?) }),
+        Quasiquote(expr) => Ok(Quasiquote(Box::new(expand_unquoted(*expr, saucy_vars)?))),
+        other => Ok(other),
+    }
+}
+
+fn expand_all(items: Vec<AstNode>, saucy_vars: &mut HashMap<String, AstNode>) -> Result<Vec<AstNode>, EvalError> {
+    items.into_iter().map(|item| expand_form(item, saucy_vars)).collect()
+}
+
+/// Inside a quasiquote only the unquoted parts are code.
+fn expand_unquoted(form: AstNode, saucy_vars: &mut HashMap<String, AstNode>) -> Result<AstNode, EvalError> {
+    let _frame = EvalFrame::enter()?;
+    match form {
+        Unquote(expr) => Ok(Unquote(Box::new(expand_form(*expr, saucy_vars)?))),
+        UnquoteSplicing(expr) => Ok(UnquoteSplicing(Box::new(expand_form(*expr, saucy_vars)?))),
+        Terms(items) => Ok(Terms(expand_unquoted_all(items, saucy_vars)?)),
+        Vector(items) => Ok(Vector(expand_unquoted_all(items, saucy_vars)?)),
+        IsGlobal { ident, expr } => Ok(IsGlobal { ident, expr: Box::new(expand_unquoted(*expr, saucy_vars)?) }),
+        Quote(expr) => Ok(Quote(Box::new(expand_unquoted(*expr, saucy_vars)?))),
+        other => Ok(other),
+    }
+}
+
+fn expand_unquoted_all(items: Vec<AstNode>, saucy_vars: &mut HashMap<String, AstNode>) -> Result<Vec<AstNode>, EvalError> {
+    items.into_iter().map(|item| expand_unquoted(item, saucy_vars)).collect()
+}
+
+fn is_form(form: &AstNode, name: &str) -> bool {
+    match form {
+        Terms(items) => matches!(items.first(), Some(Ident(head)) if head == name),
+        _ => false,
+    }
+}
+
+fn form_items(form: AstNode) -> Vec<AstNode> {
+    match form {
+        Terms(items) => items,
+        other => vec![other],
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::Arc;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::ToPrimitive;
+
+use crate::lazy::{contains_lazy, realize};
+use crate::AstNode::{self, *};
+use crate::{EvalError, Primitive};
+
+/// The signature of a Rust function callable from Sauce. Arguments arrive
+/// already evaluated.
+pub type NativeFnBody = dyn Fn(&[AstNode]) -> Result<AstNode, EvalError> + Send + Sync;
+
+/// Calls a Sauce function value with evaluated arguments.
+pub(crate) type Apply<'a> = dyn FnMut(&AstNode, Vec<AstNode>) -> Result<AstNode, EvalError> + 'a;
+
+/// A builtin that calls the functions it is passed, such as `map`.
+pub(crate) type HigherOrder = fn(&[AstNode], &mut Apply) -> Result<AstNode, EvalError>;
+
+#[derive(Clone)]
+enum Body {
+    Plain(Arc<NativeFnBody>),
+    HigherOrder(HigherOrder),
+    // A higher-order builtin with its first arguments already given.
+    Partial(HigherOrder, Vec<AstNode>),
+}
+
+/// A Rust function exposed to Sauce, usually through
+/// `Interpreter::register_fn`.
+#[derive(Clone)]
+pub struct NativeFn {
+    pub name: String,
+    func: Body,
+}
+
+impl NativeFn {
+    pub fn new<F>(name: &str, func: F) -> NativeFn
+    where
+        F: Fn(&[AstNode]) -> Result<AstNode, EvalError> + Send + Sync + 'static,
+    {
+        NativeFn { name: name.to_string(), func: Body::Plain(Arc::new(func)) }
+    }
+
+    pub(crate) fn higher_order(name: &str, func: HigherOrder) -> NativeFn {
+        NativeFn { name: name.to_string(), func: Body::HigherOrder(func) }
+    }
+
+    /// `func` with `bound` passed in front of whatever arguments it is
+    /// called with. This is how J's adverbs make a new verb out of one.
+    pub(crate) fn partial(name: &str, func: HigherOrder, bound: Vec<AstNode>) -> NativeFn {
+        NativeFn { name: name.to_string(), func: Body::Partial(func, bound) }
+    }
+
+    /// Calls the function outside of an interpreter. Sauce functions passed
+    /// to builtins like `map` can't see globals when called this way.
+    pub fn call(&self, args: &[AstNode]) -> Result<AstNode, EvalError> {
+        self.call_with(args, &mut |func, args| crate::apply(func.clone(), args, &mut HashMap::new()))
+    }
+
+    pub(crate) fn call_with(&self, args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+        match &self.func {
+            // Functions that don't take an `apply` can't realize lazy
+            // sequences themselves, so they are given lists instead.
+            Body::Plain(func) if args.iter().any(contains_lazy) => {
+                let args = args.iter().map(|arg| realize(arg.clone(), apply)).collect::<Result<Vec<_>, _>>()?;
+                func(&args)
+            }
+            Body::Plain(func) => func(args),
+            Body::HigherOrder(func) => func(args, apply),
+            Body::Partial(func, bound) => {
+                let args: Vec<AstNode> = bound.iter().chain(args).cloned().collect();
+                func(&args, apply)
+            }
+        }
+    }
+}
+
+// Two native functions are only equal if they are the same registration.
+impl PartialEq for NativeFn {
+    fn eq(&self, other: &NativeFn) -> bool {
+        match (&self.func, &other.func) {
+            (Body::Plain(x), Body::Plain(y)) => Arc::ptr_eq(x, y),
+            (Body::HigherOrder(_), Body::HigherOrder(_)) => self.name == other.name,
+            (Body::Partial(_, x), Body::Partial(_, y)) => self.name == other.name && x == y,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for NativeFn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NativeFn({})", self.name)
+    }
+}
+
+impl From<i32> for Primitive {
+    fn from(value: i32) -> Self {
+        Primitive::Integer(value.into())
+    }
+}
+
+impl From<i64> for Primitive {
+    fn from(value: i64) -> Self {
+        Primitive::Integer(value)
+    }
+}
+
+/// Bignums small enough for an `i64` become plain integers.
+impl From<BigInt> for Primitive {
+    fn from(value: BigInt) -> Self {
+        match i64::try_from(&value) {
+            Ok(value) => Primitive::Integer(value),
+            Err(_) => Primitive::BigInteger(value),
+        }
+    }
+}
+
+impl From<f64> for Primitive {
+    fn from(value: f64) -> Self {
+        Primitive::DoublePrecisionFloat(value)
+    }
+}
+
+/// Ratios with a denominator of 1 become integers.
+impl From<BigRational> for Primitive {
+    fn from(value: BigRational) -> Self {
+        if value.is_integer() {
+            Primitive::from(value.to_integer())
+        } else {
+            Primitive::Ratio(value)
+        }
+    }
+}
+
+impl From<String> for Primitive {
+    fn from(value: String) -> Self {
+        Primitive::Str(value)
+    }
+}
+
+impl From<&str> for Primitive {
+    fn from(value: &str) -> Self {
+        Primitive::Str(value.to_string())
+    }
+}
+
+impl<T: Into<Primitive>> From<Vec<T>> for Primitive {
+    fn from(values: Vec<T>) -> Self {
+        Primitive::Vector(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<Primitive> for AstNode {
+    fn from(value: Primitive) -> Self {
+        Value(value)
+    }
+}
+
+impl From<i32> for AstNode {
+    fn from(value: i32) -> Self {
+        Value(value.into())
+    }
+}
+
+impl From<i64> for AstNode {
+    fn from(value: i64) -> Self {
+        Value(value.into())
+    }
+}
+
+impl From<BigInt> for AstNode {
+    fn from(value: BigInt) -> Self {
+        Value(value.into())
+    }
+}
+
+impl From<BigRational> for AstNode {
+    fn from(value: BigRational) -> Self {
+        Value(value.into())
+    }
+}
+
+impl From<f64> for AstNode {
+    fn from(value: f64) -> Self {
+        Value(value.into())
+    }
+}
+
+impl From<String> for AstNode {
+    fn from(value: String) -> Self {
+        Value(value.into())
+    }
+}
+
+impl From<&str> for AstNode {
+    fn from(value: &str) -> Self {
+        Value(value.into())
+    }
+}
+
+/// Rust vectors become Sauce lists.
+impl<T: Into<AstNode>> From<Vec<T>> for AstNode {
+    fn from(values: Vec<T>) -> Self {
+        Terms(values.into_iter().map(Into::into).collect())
+    }
+}
+
+pub(crate) fn mismatch(expected: &str, found: &AstNode) -> EvalError {
+    EvalError::TypeMismatch { expected: expected.to_string(), found: Box::new(found.clone()) }
+}
+
+impl TryFrom<&AstNode> for i64 {
+    type Error = EvalError;
+
+    fn try_from(node: &AstNode) -> Result<Self, Self::Error> {
+        match node {
+            Value(Primitive::Integer(value)) => Ok(*value),
+            other => Err(mismatch("an integer", other)),
+        }
+    }
+}
+
+impl TryFrom<&AstNode> for i32 {
+    type Error = EvalError;
+
+    fn try_from(node: &AstNode) -> Result<Self, Self::Error> {
+        match node {
+            Value(Primitive::Integer(value)) => i32::try_from(*value).map_err(|_| mismatch("a 32-bit integer", node)),
+            other => Err(mismatch("an integer", other)),
+        }
+    }
+}
+
+impl TryFrom<&AstNode> for BigInt {
+    type Error = EvalError;
+
+    fn try_from(node: &AstNode) -> Result<Self, Self::Error> {
+        match node {
+            Value(Primitive::Integer(value)) => Ok(BigInt::from(*value)),
+            Value(Primitive::BigInteger(value)) => Ok(value.clone()),
+            other => Err(mismatch("an integer", other)),
+        }
+    }
+}
+
+/// Integers are widened, as the arithmetic operators do.
+impl TryFrom<&AstNode> for f64 {
+    type Error = EvalError;
+
+    fn try_from(node: &AstNode) -> Result<Self, Self::Error> {
+        match node {
+            Value(Primitive::DoublePrecisionFloat(value)) => Ok(*value),
+            Value(Primitive::Integer(value)) => Ok(*value as f64),
+            Value(Primitive::BigInteger(value)) => Ok(value.to_f64().unwrap_or(f64::NAN)),
+            Value(Primitive::Ratio(value)) => Ok(value.to_f64().unwrap_or(f64::NAN)),
+            other => Err(mismatch("a number", other)),
+        }
+    }
+}
+
+impl TryFrom<&AstNode> for String {
+    type Error = EvalError;
+
+    fn try_from(node: &AstNode) -> Result<Self, Self::Error> {
+        match node {
+            Value(Primitive::Str(value)) => Ok(value.clone()),
+            other => Err(mismatch("a string", other)),
+        }
+    }
+}
+
+/// Accepts both Sauce lists and vectors.
+impl<T> TryFrom<&AstNode> for Vec<T>
+where
+    T: for<'a> TryFrom<&'a AstNode, Error = EvalError>,
+{
+    type Error = EvalError;
+
+    fn try_from(node: &AstNode) -> Result<Self, Self::Error> {
+        match node {
+            Terms(items) => items.iter().map(T::try_from).collect(),
+            Value(Primitive::Vector(items)) => items.iter().map(|item| T::try_from(&Value(item.clone()))).collect(),
+            other => Err(mismatch("a list", other)),
+        }
+    }
+}
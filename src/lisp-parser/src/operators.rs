@@ -1,19 +1,44 @@
+use std::cmp::Ordering;
 use std::fmt;
 use std::error::Error;
 
+use crate::broadcast::elementwise;
+use crate::{Array, Pattern};
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{ToPrimitive, Zero};
+
+/// Numbers, strings, the flat vectors the operators broadcast over, and
+/// arrays of more dimensions.
+/// Variants may be added, so matches outside this crate need a wildcard arm.
 #[derive(PartialEq, Debug, Clone)]
+#[non_exhaustive]
 pub enum Primitive {
-    Integer(i32),
+    Integer(i64),
+    /// Integers that don't fit in 64 bits. Arithmetic on `Integer`s promotes
+    /// to this on overflow, and results that fit are demoted back again.
+    BigInteger(BigInt),
+    /// The exact result of dividing integers that don't divide evenly.
+    /// Ratios are always in lowest terms, and become integers again when the
+    /// denominator reaches 1.
+    Ratio(BigRational),
     DoublePrecisionFloat(f64),
     Str(String),
     Vector(Vec<Primitive>),
+    Regex(Pattern),
+    Array(Array),
 }
 impl std::fmt::Display for Primitive {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Primitive::Integer(item) => write!(f, "{}", item),
+            Primitive::BigInteger(item) => write!(f, "{}", item),
+            Primitive::Ratio(item) => write!(f, "{}", item),
             Primitive::DoublePrecisionFloat(item) => write!(f, "{}", item),
-            Primitive::Str(item) => write!(f, "\'{}\'", item),
+            Primitive::Str(item) => write!(f, "{}", escape(item)),
+            Primitive::Regex(item) => write!(f, "{}", item),
+            Primitive::Array(item) => write!(f, "{}", item),
             Primitive::Vector(item) => {
                 write!(f, "[")?;
                 for (i, e) in item.iter().enumerate() {
@@ -28,10 +53,81 @@ impl std::fmt::Display for Primitive {
     }
 }
 
+/// Writes a string as a double-quoted literal that reads back as the same
+/// string.
+fn escape(s: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '\n' => literal.push_str("\\n"),
+            '\t' => literal.push_str("\\t"),
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            c if c.is_control() => literal.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+// The string operators below count characters, never bytes, so they are
+// safe on any UTF-8 text.
+
+/// `"abc" * "xy"` is `"axbyc"`.
+fn interleave(a: &str, b: &str) -> String {
+    let mut result = String::new();
+    let (mut a, mut b) = (a.chars(), b.chars());
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return result,
+            (x, y) => result.extend(x.into_iter().chain(y)),
+        }
+    }
+}
+
+/// The longest string that repeating one may make, in characters. Longer
+/// results are an error rather than a capacity overflow or running out of
+/// memory.
+const MAX_REPEAT_LENGTH: usize = 1 << 26;
+
+/// Repeats `s` a whole number of times. Negative counts repeat it none.
+fn repeat_whole(s: &str, times: i64) -> Result<String, OperationError> {
+    let times = times.max(0) as usize;
+    match s.chars().count().checked_mul(times) {
+        Some(length) if length <= MAX_REPEAT_LENGTH => Ok(s.repeat(times)),
+        _ => Err(too_long()),
+    }
+}
+
+/// Repeats `s` a fractional number of times, so `1.5 * "ab"` is `"aba"`.
+fn repeat_fraction(s: &str, times: f64) -> Result<String, OperationError> {
+    let count = (times * s.chars().count() as f64).floor();
+    if count.is_nan() || count <= 0.0 {
+        return Ok(String::new());
+    }
+    if count > MAX_REPEAT_LENGTH as f64 {
+        return Err(too_long());
+    }
+    Ok(s.chars().cycle().take(count as usize).collect())
+}
+
+fn too_long() -> OperationError {
+    OperationError::from(format!("Cannot repeat a string to more than {} characters", MAX_REPEAT_LENGTH).as_str())
+}
+
+/// The first of `parts` equal pieces of `s`, rounded up to whole characters.
+fn divide_chars(s: &str, parts: f64) -> String {
+    if parts.is_nan() || parts <= 0.0 {
+        return String::new();
+    }
+    let count = (s.chars().count() as f64 / parts).ceil();
+    s.chars().take(count as usize).collect()
+}
 
 use std::ops;
 
-#[derive(Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct OperationError {
     message: String,
 }
@@ -51,154 +147,133 @@ impl From<&str> for OperationError {
     }
 }
 
+// The operator impls broadcast over vectors with `elementwise`, which fails
+// when their lengths can't be matched up. Operands that arithmetic isn't
+// defined for, such as regexes, are an error too.
 impl ops::Add for Primitive {
-	type Output = Primitive;
+    type Output = Result<Primitive, OperationError>;
 
-    fn add(self, other: Primitive) -> Primitive {
-        match (self, other) {
-            (Primitive::Integer(x), Primitive::Integer(y)) => Primitive::Integer(x + y),
+    fn add(self, other: Primitive) -> Self::Output {
+        Ok(match (self, other) {
+            (x @ Primitive::Vector(_), y) | (x, y @ Primitive::Vector(_)) => return elementwise(x, y, |x, y| x + y),
+            (Primitive::Integer(x), Primitive::Integer(y)) => x.checked_add(y).map(Primitive::Integer).unwrap_or_else(|| Primitive::from(BigInt::from(x) + y)),
+            (Primitive::BigInteger(x), Primitive::BigInteger(y)) => Primitive::from(x + y),
+            (Primitive::BigInteger(x), Primitive::Integer(y)) => Primitive::from(x + y),
+            (Primitive::Integer(x), Primitive::BigInteger(y)) => Primitive::from(x + y),
+            (Primitive::BigInteger(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(to_f64(&x) + y),
+            (Primitive::DoublePrecisionFloat(x), Primitive::BigInteger(y)) => Primitive::DoublePrecisionFloat(x + to_f64(&y)),
+            (x @ Primitive::Ratio(_), y @ (Primitive::Integer(_) | Primitive::BigInteger(_) | Primitive::Ratio(_)))
+            | (x @ (Primitive::Integer(_) | Primitive::BigInteger(_)), y @ Primitive::Ratio(_)) => Primitive::from(exact(&x) + exact(&y)),
+            (Primitive::Ratio(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(ratio_to_f64(&x) + y),
+            (Primitive::DoublePrecisionFloat(x), Primitive::Ratio(y)) => Primitive::DoublePrecisionFloat(x + ratio_to_f64(&y)),
+            (Primitive::Ratio(x), Primitive::Str(y)) => Primitive::Str(format!("{}{}", x, y)),
+            (Primitive::Str(x), Primitive::Ratio(y)) => Primitive::Str(format!("{}{}", x, y)),
             (Primitive::DoublePrecisionFloat(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(x + y),
             (Primitive::Str(x), Primitive::Str(y)) => Primitive::Str(x + &y),
-            (Primitive::Vector(a), Primitive::Vector(b)) => {
-                let mut result = Vec::new();
-                let mut longer = a.clone();
-                let mut shorter = b.clone();
-                if a.len() < b.len() {
-                    longer = b.clone();
-                    shorter = a.clone();
-                }
-                for (x, y) in shorter.iter().zip(longer.iter()) {
-                    result.push(x.clone() + y.clone());
-                }
-                result.extend(longer[shorter.len()..].iter().cloned());
-                Primitive::Vector(result)
-            },
             (Primitive::Integer(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(x as f64 + y),
             (Primitive::DoublePrecisionFloat(x), Primitive::Integer(y)) => Primitive::DoublePrecisionFloat(x + y as f64),
             (Primitive::Integer(x), Primitive::Str(y)) => Primitive::Str(format!("{}{}", x, y)),
             (Primitive::Str(x), Primitive::Integer(y)) => Primitive::Str(format!("{}{}", x, y)),
+            (Primitive::BigInteger(x), Primitive::Str(y)) => Primitive::Str(format!("{}{}", x, y)),
+            (Primitive::Str(x), Primitive::BigInteger(y)) => Primitive::Str(format!("{}{}", x, y)),
             (Primitive::DoublePrecisionFloat(x), Primitive::Str(y)) => Primitive::Str(format!("{}{}", x, y)),
             (Primitive::Str(x), Primitive::DoublePrecisionFloat(y)) => Primitive::Str(format!("{}{}", x, y)),
-            (Primitive::Integer(value), Primitive::Vector(y)) => Primitive::Vector(y.iter().map(|x| x.clone() + Primitive::Integer(value)).collect()),
-            (Primitive::DoublePrecisionFloat(value), Primitive::Vector(y)) => Primitive::Vector(y.iter().map(|x| x.clone() + Primitive::DoublePrecisionFloat(value)).collect()),
-            (Primitive::Str(value), Primitive::Vector(y)) => Primitive::Vector(y.iter().map(|x| x.clone() + Primitive::Str(value.clone())).collect()),
-            (Primitive::Vector(y), Primitive::Integer(value)) => Primitive::Vector(y.iter().map(|x| x.clone() + Primitive::Integer(value)).collect()),
-            (Primitive::Vector(y), Primitive::DoublePrecisionFloat(value)) => Primitive::Vector(y.iter().map(|x| x.clone() + Primitive::DoublePrecisionFloat(value)).collect()),
-            (Primitive::Vector(y), Primitive::Str(value)) => Primitive::Vector(y.iter().map(|x| x.clone() + Primitive::Str(value.clone())).collect()),
-    }
+            (x, y) => return Err(unsupported("+", &x, &y)),
+        })
     }
 }
 
 impl ops::Sub for Primitive {
-	type Output = Primitive;
+    type Output = Result<Primitive, OperationError>;
 
-    fn sub(self, other: Primitive) -> Primitive {
-        match (self, other) {
-            (Primitive::Integer(x), Primitive::Integer(y)) => Primitive::Integer(x - y),
+    fn sub(self, other: Primitive) -> Self::Output {
+        Ok(match (self, other) {
+            (x @ Primitive::Vector(_), y) | (x, y @ Primitive::Vector(_)) => return elementwise(x, y, |x, y| x - y),
+            (Primitive::Integer(x), Primitive::Integer(y)) => x.checked_sub(y).map(Primitive::Integer).unwrap_or_else(|| Primitive::from(BigInt::from(x) - y)),
+            (Primitive::BigInteger(x), Primitive::BigInteger(y)) => Primitive::from(x - y),
+            (Primitive::BigInteger(x), Primitive::Integer(y)) => Primitive::from(x - y),
+            (Primitive::Integer(x), Primitive::BigInteger(y)) => Primitive::from(x - y),
+            (Primitive::BigInteger(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(to_f64(&x) - y),
+            (Primitive::DoublePrecisionFloat(x), Primitive::BigInteger(y)) => Primitive::DoublePrecisionFloat(x - to_f64(&y)),
+            (x @ Primitive::Ratio(_), y @ (Primitive::Integer(_) | Primitive::BigInteger(_) | Primitive::Ratio(_)))
+            | (x @ (Primitive::Integer(_) | Primitive::BigInteger(_)), y @ Primitive::Ratio(_)) => Primitive::from(exact(&x) - exact(&y)),
+            (Primitive::Ratio(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(ratio_to_f64(&x) - y),
+            (Primitive::DoublePrecisionFloat(x), Primitive::Ratio(y)) => Primitive::DoublePrecisionFloat(x - ratio_to_f64(&y)),
+            (Primitive::Ratio(x), Primitive::Str(y)) => Primitive::Str((x.to_string()).replace(&y,"")),
+            (Primitive::Str(x), Primitive::Ratio(y)) => Primitive::Str(x.replace(&y.to_string(),"")),
             (Primitive::DoublePrecisionFloat(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(x - y),
             (Primitive::Str(x), Primitive::Str(y)) => Primitive::Str(x.replace(&y,"")),
-            (Primitive::Vector(a), Primitive::Vector(b)) => {
-                let mut result = Vec::new();
-                let mut longer = a.clone();
-                let mut shorter = b.clone();
-                if a.len() < b.len() {
-                    longer = b.clone();
-                    shorter = a.clone();
-                }
-                for (x, y) in shorter.iter().zip(longer.iter()) {
-                    result.push(x.clone() - y.clone());
-                }
-                result.extend(longer[shorter.len()..].iter().cloned());
-                Primitive::Vector(result)
-            },
             (Primitive::Integer(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(x as f64 - y),
             (Primitive::DoublePrecisionFloat(x), Primitive::Integer(y)) => Primitive::DoublePrecisionFloat(x - y as f64),
             (Primitive::Integer(x), Primitive::Str(y)) => Primitive::Str((x.to_string()).replace(&y,"")),
             (Primitive::Str(x), Primitive::Integer(y)) => Primitive::Str(x.replace(&y.to_string(),"")),
+            (Primitive::BigInteger(x), Primitive::Str(y)) => Primitive::Str((x.to_string()).replace(&y,"")),
+            (Primitive::Str(x), Primitive::BigInteger(y)) => Primitive::Str(x.replace(&y.to_string(),"")),
             (Primitive::DoublePrecisionFloat(x), Primitive::Str(y)) => Primitive::Str((x.to_string()).replace(&y,"")),
             (Primitive::Str(x), Primitive::DoublePrecisionFloat(y)) => Primitive::Str(x.replace(&y.to_string(),"")),
-            (Primitive::Integer(value), Primitive::Vector(y)) => Primitive::Vector(y.iter().map(|x| x.clone() - Primitive::Integer(value)).collect()),
-            (Primitive::DoublePrecisionFloat(value), Primitive::Vector(y)) => Primitive::Vector(y.iter().map(|x| x.clone() - Primitive::DoublePrecisionFloat(value)).collect()),
-            (Primitive::Str(value), Primitive::Vector(y)) => Primitive::Vector(y.iter().map(|x| x.clone() - Primitive::Str(value.clone())).collect()),
-            (Primitive::Vector(y), Primitive::Integer(value)) => Primitive::Vector(y.iter().map(|x| x.clone() - Primitive::Integer(value)).collect()),
-            (Primitive::Vector(y), Primitive::DoublePrecisionFloat(value)) => Primitive::Vector(y.iter().map(|x| x.clone() - Primitive::DoublePrecisionFloat(value)).collect()),
-            (Primitive::Vector(y), Primitive::Str(value)) => Primitive::Vector(y.iter().map(|x| x.clone() - Primitive::Str(value.clone())).collect()),
-    }
+            (x, y) => return Err(unsupported("-", &x, &y)),
+        })
     }
 }
 
 impl ops::Mul for Primitive {
-	type Output = Primitive;
+    type Output = Result<Primitive, OperationError>;
 
-    fn mul(self, other: Primitive) -> Primitive {
-        match (self, other) {
-            (Primitive::Integer(x), Primitive::Integer(y)) => Primitive::Integer(x * y),
+    fn mul(self, other: Primitive) -> Self::Output {
+        Ok(match (self, other) {
+            (x @ Primitive::Vector(_), y) | (x, y @ Primitive::Vector(_)) => return elementwise(x, y, |x, y| x * y),
+            (Primitive::Integer(x), Primitive::Integer(y)) => x.checked_mul(y).map(Primitive::Integer).unwrap_or_else(|| Primitive::from(BigInt::from(x) * y)),
+            (Primitive::BigInteger(x), Primitive::BigInteger(y)) => Primitive::from(x * y),
+            (Primitive::BigInteger(x), Primitive::Integer(y)) => Primitive::from(x * y),
+            (Primitive::Integer(x), Primitive::BigInteger(y)) => Primitive::from(x * y),
+            (Primitive::BigInteger(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(to_f64(&x) * y),
+            (Primitive::DoublePrecisionFloat(x), Primitive::BigInteger(y)) => Primitive::DoublePrecisionFloat(x * to_f64(&y)),
+            (x @ Primitive::Ratio(_), y @ (Primitive::Integer(_) | Primitive::BigInteger(_) | Primitive::Ratio(_)))
+            | (x @ (Primitive::Integer(_) | Primitive::BigInteger(_)), y @ Primitive::Ratio(_)) => Primitive::from(exact(&x) * exact(&y)),
+            (Primitive::Ratio(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(ratio_to_f64(&x) * y),
+            (Primitive::DoublePrecisionFloat(x), Primitive::Ratio(y)) => Primitive::DoublePrecisionFloat(x * ratio_to_f64(&y)),
+            // Strings treat a ratio like the float it approximates.
+            (Primitive::Ratio(x), Primitive::Str(y)) => return Primitive::DoublePrecisionFloat(ratio_to_f64(&x)) * Primitive::Str(y),
+            (Primitive::Str(x), Primitive::Ratio(y)) => return Primitive::Str(x) * Primitive::DoublePrecisionFloat(ratio_to_f64(&y)),
             (Primitive::DoublePrecisionFloat(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(x * y),
-            (Primitive::Str(a), Primitive::Str(b)) => {
-                let mut result = String::new();
-                for (x, y) in a.chars().zip(b.chars()) {
-                    result.push(x);
-                    result.push(y);
-                }
-                if a.len() > b.len() {
-                    result.push_str(&a[b.len()..]);
-                }
-                if b.len() > a.len() {
-                    result.push_str(&a[b.len()..]);
-                }
-                
-                Primitive::Str(result)
-            },
-            (Primitive::Vector(a), Primitive::Vector(b)) => {
-                let mut result = Vec::new();
-                let mut longer = a.clone();
-                let mut shorter = b.clone();
-                if a.len() < b.len() {
-                    longer = b.clone();
-                    shorter = a.clone();
-                }
-                for (x, y) in shorter.iter().zip(longer.iter()) {
-                    result.push(x.clone() * y.clone());
-                }
-                result.extend(longer[shorter.len()..].iter().cloned());
-                Primitive::Vector(result)
-            },
+            (Primitive::Str(a), Primitive::Str(b)) => Primitive::Str(interleave(&a, &b)),
             (Primitive::Integer(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(x as f64 * y),
             (Primitive::DoublePrecisionFloat(x), Primitive::Integer(y)) => Primitive::DoublePrecisionFloat(x * y as f64),
-            (Primitive::Integer(x), Primitive::Str(y)) => Primitive::Str(y.repeat(x as usize)),
-            (Primitive::Str(x), Primitive::Integer(y)) => Primitive::Str(x.replace(&y.to_string(),"")),
-            (Primitive::DoublePrecisionFloat(x), Primitive::Str(y)) => {
-                let i = x.ceil() as i32;
-                let result = y.repeat(i as usize);
-                let fraction = (x/x.ceil()) * result.len() as f64;
-                let final_pos = fraction.floor() as i32;
-                let final_u = final_pos as usize;
-                Primitive::Str(result[0..final_u].to_string())
-            },
-            (Primitive::Str(y), Primitive::DoublePrecisionFloat(x)) => {
-                let i = x.ceil() as i32;
-                let result = y.repeat(i as usize);
-                let fraction = (x/x.ceil()) * result.len() as f64;
-                let final_pos = fraction.floor() as i32;
-                let final_u = final_pos as usize;
-                Primitive::Str(result[0..final_u].to_string())
-            },
-            (Primitive::Integer(value), Primitive::Vector(y)) => Primitive::Vector(y.iter().map(|x| x.clone() * Primitive::Integer(value)).collect()),
-            (Primitive::DoublePrecisionFloat(value), Primitive::Vector(y)) => Primitive::Vector(y.iter().map(|x| x.clone() * Primitive::DoublePrecisionFloat(value)).collect()),
-            (Primitive::Str(value), Primitive::Vector(y)) => Primitive::Vector(y.iter().map(|x| x.clone() * Primitive::Str(value.clone())).collect()),
-            (Primitive::Vector(y), Primitive::Integer(value)) => Primitive::Vector(y.iter().map(|x| x.clone() * Primitive::Integer(value)).collect()),
-            (Primitive::Vector(y), Primitive::DoublePrecisionFloat(value)) => Primitive::Vector(y.iter().map(|x| x.clone() * Primitive::DoublePrecisionFloat(value)).collect()),
-            (Primitive::Vector(y), Primitive::Str(value)) => Primitive::Vector(y.iter().map(|x| x.clone() * Primitive::Str(value.clone())).collect()),
-    }
+            (Primitive::Integer(x), Primitive::Str(y)) => Primitive::Str(repeat_whole(&y, x)?),
+            (Primitive::Str(x), Primitive::Integer(y)) => Primitive::Str(repeat_whole(&x, y)?),
+            (Primitive::BigInteger(x), Primitive::Str(y)) => Primitive::Str(repeat_fraction(&y, to_f64(&x))?),
+            (Primitive::Str(x), Primitive::BigInteger(y)) => Primitive::Str(repeat_fraction(&x, to_f64(&y))?),
+            (Primitive::DoublePrecisionFloat(x), Primitive::Str(y)) => Primitive::Str(repeat_fraction(&y, x)?),
+            (Primitive::Str(y), Primitive::DoublePrecisionFloat(x)) => Primitive::Str(repeat_fraction(&y, x)?),
+            (x, y) => return Err(unsupported("*", &x, &y)),
+        })
     }
 }
 
 impl ops::Div for Primitive {
-	type Output = Primitive;
+    type Output = Result<Primitive, OperationError>;
 
-    fn div(self, other: Primitive) -> Primitive {
-        match (self, other) {
-            (Primitive::Integer(x), Primitive::Integer(y)) => Primitive::Integer(x / y),
+    fn div(self, other: Primitive) -> Self::Output {
+        Ok(match (self, other) {
+            (x @ Primitive::Vector(_), y) | (x, y @ Primitive::Vector(_)) => return elementwise(x, y, |x, y| x / y),
+            // Floats follow IEEE 754 instead, so `(/ 1.0 0)` is infinite.
+            (Primitive::Integer(_) | Primitive::BigInteger(_) | Primitive::Ratio(_) | Primitive::Str(_), Primitive::Integer(0)) => {
+                return Err(OperationError::from("Divide by zero"))
+            }
+            // Like Clojure, integers that don't divide evenly make a ratio.
+            (Primitive::Integer(x), Primitive::Integer(y)) if x.checked_rem(y) == Some(0) => Primitive::Integer(x / y),
+            (x @ (Primitive::Integer(_) | Primitive::BigInteger(_)), y @ (Primitive::Integer(_) | Primitive::BigInteger(_))) => {
+                return quotient(exact(&x), exact(&y))
+            }
+            (Primitive::BigInteger(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(to_f64(&x) / y),
+            (Primitive::DoublePrecisionFloat(x), Primitive::BigInteger(y)) => Primitive::DoublePrecisionFloat(x / to_f64(&y)),
+            (x @ Primitive::Ratio(_), y @ (Primitive::Integer(_) | Primitive::BigInteger(_) | Primitive::Ratio(_)))
+            | (x @ (Primitive::Integer(_) | Primitive::BigInteger(_)), y @ Primitive::Ratio(_)) => return quotient(exact(&x), exact(&y)),
+            (Primitive::Ratio(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(ratio_to_f64(&x) / y),
+            (Primitive::DoublePrecisionFloat(x), Primitive::Ratio(y)) => Primitive::DoublePrecisionFloat(x / ratio_to_f64(&y)),
+            // Strings treat a ratio like the float it approximates.
+            (Primitive::Ratio(x), Primitive::Str(y)) => return Primitive::DoublePrecisionFloat(ratio_to_f64(&x)) / Primitive::Str(y),
+            (Primitive::Str(x), Primitive::Ratio(y)) => return Primitive::Str(x) / Primitive::DoublePrecisionFloat(ratio_to_f64(&y)),
             (Primitive::DoublePrecisionFloat(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(x / y),
             (Primitive::Str(a), Primitive::Str(b)) => {
                 let mut result = Vec::new();
@@ -208,72 +283,299 @@ impl ops::Div for Primitive {
                 }
                 Primitive::Vector(result)
             },
-            (Primitive::Vector(a), Primitive::Vector(b)) => {
-                let mut result = Vec::new();
-                let mut longer = a.clone();
-                let mut shorter = b.clone();
-                if a.len() < b.len() {
-                    longer = b.clone();
-                    shorter = a.clone();
-                }
-                for (x, y) in shorter.iter().zip(longer.iter()) {
-                    result.push(x.clone() / y.clone());
-                }
-                result.extend(longer[shorter.len()..].iter().cloned());
-                Primitive::Vector(result)
-            },
             (Primitive::Integer(x), Primitive::DoublePrecisionFloat(y)) => Primitive::DoublePrecisionFloat(x as f64 / y),
             (Primitive::DoublePrecisionFloat(x), Primitive::Integer(y)) => Primitive::DoublePrecisionFloat(x / y as f64),
-            (Primitive::Integer(x), Primitive::Str(y)) => {
-                let sub_len = y.len() / x as usize;
-                let mut result = String::new();
-                for (i, c) in y.chars().enumerate() {
-                    if i <= sub_len {
-                        result.push(c);
-                    }
-                }
-                Primitive::Str(result)
-            },
-            (Primitive::Str(y), Primitive::Integer(x)) => {
-                let sub_len = y.len() / x as usize;
-                let mut result = String::new();
-                for (i, c) in y.chars().enumerate() {
-                    if i <= sub_len {
-                        result.push(c);
-                    }
-                }
-                Primitive::Str(result)
-            },
-            (Primitive::DoublePrecisionFloat(x), Primitive::Str(y)) => {
-                let l = y.len() as i32;
-                let sub_len = l as f64 / x;
-                let size = sub_len.ceil() as i32 as usize;
-                let mut result = String::new();
-                for (i, c) in y.chars().enumerate() {
-                    if i <= size {
-                        result.push(c);
-                    }
-                }
-                Primitive::Str(result)
-            },
-            (Primitive::Str(y), Primitive::DoublePrecisionFloat(x)) => {
-                let l = y.len() as i32;
-                let sub_len = l as f64 / x;
-                let size = sub_len.ceil() as i32 as usize;
-                let mut result = String::new();
-                for (i, c) in y.chars().enumerate() {
-                    if i <= size {
-                        result.push(c);
-                    }
-                }
-                Primitive::Str(result)
+            (Primitive::Integer(x), Primitive::Str(y)) => Primitive::Str(divide_chars(&y, x as f64)),
+            (Primitive::Str(y), Primitive::Integer(x)) => Primitive::Str(divide_chars(&y, x as f64)),
+            (Primitive::BigInteger(x), Primitive::Str(y)) | (Primitive::Str(y), Primitive::BigInteger(x)) => {
+                Primitive::Str(divide_chars(&y, to_f64(&x)))
             },
-            (Primitive::Integer(value), Primitive::Vector(y)) => Primitive::Vector(y.iter().map(|x| x.clone() / Primitive::Integer(value)).collect()),
-            (Primitive::DoublePrecisionFloat(value), Primitive::Vector(y)) => Primitive::Vector(y.iter().map(|x| x.clone() / Primitive::DoublePrecisionFloat(value)).collect()),
-            (Primitive::Str(value), Primitive::Vector(y)) => Primitive::Vector(y.iter().map(|x| x.clone() / Primitive::Str(value.clone())).collect()),
-            (Primitive::Vector(y), Primitive::Integer(value)) => Primitive::Vector(y.iter().map(|x| x.clone() / Primitive::Integer(value)).collect()),
-            (Primitive::Vector(y), Primitive::DoublePrecisionFloat(value)) => Primitive::Vector(y.iter().map(|x| x.clone() / Primitive::DoublePrecisionFloat(value)).collect()),
-            (Primitive::Vector(y), Primitive::Str(value)) => Primitive::Vector(y.iter().map(|x| x.clone() / Primitive::Str(value.clone())).collect()),
+            (Primitive::DoublePrecisionFloat(x), Primitive::Str(y)) => Primitive::Str(divide_chars(&y, x)),
+            (Primitive::Str(y), Primitive::DoublePrecisionFloat(x)) => Primitive::Str(divide_chars(&y, x)),
+            (x, y) => return Err(unsupported("/", &x, &y)),
+        })
     }
+}
+
+impl Primitive {
+    /// Following J, zero is false. Every other value is true.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Primitive::Integer(0)) && *self != Primitive::DoublePrecisionFloat(0.0)
+    }
+}
+
+impl PartialOrd for Primitive {
+    fn partial_cmp(&self, other: &Primitive) -> Option<Ordering> {
+        match (self, other) {
+            (Primitive::Integer(x), Primitive::Integer(y)) => x.partial_cmp(y),
+            (Primitive::DoublePrecisionFloat(x), Primitive::DoublePrecisionFloat(y)) => x.partial_cmp(y),
+            (Primitive::Integer(x), Primitive::DoublePrecisionFloat(y)) => (*x as f64).partial_cmp(y),
+            (Primitive::DoublePrecisionFloat(x), Primitive::Integer(y)) => x.partial_cmp(&(*y as f64)),
+            (Primitive::BigInteger(x), Primitive::BigInteger(y)) => x.partial_cmp(y),
+            (Primitive::BigInteger(x), Primitive::Integer(y)) => x.partial_cmp(&BigInt::from(*y)),
+            (Primitive::Integer(x), Primitive::BigInteger(y)) => BigInt::from(*x).partial_cmp(y),
+            (Primitive::BigInteger(x), Primitive::DoublePrecisionFloat(y)) => to_f64(x).partial_cmp(y),
+            (Primitive::DoublePrecisionFloat(x), Primitive::BigInteger(y)) => x.partial_cmp(&to_f64(y)),
+            (Primitive::Ratio(x), Primitive::DoublePrecisionFloat(y)) => ratio_to_f64(x).partial_cmp(y),
+            (Primitive::DoublePrecisionFloat(x), Primitive::Ratio(y)) => x.partial_cmp(&ratio_to_f64(y)),
+            (Primitive::Ratio(_), Primitive::Integer(_) | Primitive::BigInteger(_) | Primitive::Ratio(_))
+            | (Primitive::Integer(_) | Primitive::BigInteger(_), Primitive::Ratio(_)) => exact(self).partial_cmp(&exact(other)),
+            (Primitive::Str(x), Primitive::Str(y)) => x.partial_cmp(y),
+            (Primitive::Vector(x), Primitive::Vector(y)) => x.partial_cmp(y),
+            (Primitive::Array(x), Primitive::Array(y)) => x.to_nested().partial_cmp(&y.to_nested()),
+            _ => None,
+        }
     }
-}
\ No newline at end of file
+}
+
+// The operators below are registered as builtins in `builtins.rs`.
+// Arithmetic folds from the left like Clojure's `(+ 1 2 3)`, and
+// comparisons hold when they hold for every neighbouring pair, returning 1
+// or 0.
+
+/// Sums start from the first argument rather than from 0, so that adding
+/// strings doesn't prefix them with "0".
+pub(crate) fn add(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    check_operands("+", &args)?;
+    let mut args = args.into_iter();
+    let first = args.next().unwrap_or(Primitive::Integer(0));
+    args.try_fold(first, |x, y| x + y)
+}
+
+pub(crate) fn multiply(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    check_operands("*", &args)?;
+    let mut args = args.into_iter();
+    let first = args.next().unwrap_or(Primitive::Integer(1));
+    args.try_fold(first, |x, y| x * y)
+}
+
+/// With a single argument, `-` negates.
+pub(crate) fn subtract(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    fold_inverse("-", args, Primitive::Integer(0), |x, y| x - y)
+}
+
+/// With a single argument, `/` takes the reciprocal. Dividing by an integer
+/// zero is an error, whether alone or in a vector.
+pub(crate) fn divide(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    fold_inverse("/", args, Primitive::Integer(1), |x, y| x / y)
+}
+
+pub(crate) fn raise(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    let mut args = args.into_iter();
+    let first = args.next().ok_or_else(|| OperationError::from("^ expects at least one argument"))?;
+    args.try_fold(first, |x, y| elementwise(x, y, power))
+}
+
+pub(crate) fn greater_than(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    ordered(args, |ordering| ordering == Ordering::Greater)
+}
+
+pub(crate) fn greater_than_equal(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    ordered(args, |ordering| ordering != Ordering::Less)
+}
+
+pub(crate) fn less_than(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    ordered(args, |ordering| ordering == Ordering::Less)
+}
+
+pub(crate) fn less_than_equal(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    ordered(args, |ordering| ordering != Ordering::Greater)
+}
+
+pub(crate) fn equal(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    pairwise(args, |x, y| Ok(equivalent(x, y)))
+}
+
+pub(crate) fn not_equal(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    pairwise(args, |x, y| Ok(!equivalent(x, y)))
+}
+
+pub(crate) fn or(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    Ok(Primitive::Integer(args.iter().any(Primitive::is_truthy) as i64))
+}
+
+pub(crate) fn and(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    Ok(Primitive::Integer(args.iter().all(Primitive::is_truthy) as i64))
+}
+
+pub(crate) fn tally(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    let count = match args.as_slice() {
+        [Primitive::Vector(items)] => items.len(),
+        [Primitive::Str(s)] => s.chars().count(),
+        _ => args.len(),
+    };
+    Ok(Primitive::Integer(count as i64))
+}
+
+pub(crate) fn numerator(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    match args.as_slice() {
+        [Primitive::Ratio(x)] => Ok(Primitive::from(x.numer().clone())),
+        [x @ (Primitive::Integer(_) | Primitive::BigInteger(_))] => Ok(x.clone()),
+        _ => Err(OperationError::from("numerator expects one rational number")),
+    }
+}
+
+pub(crate) fn denominator(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    match args.as_slice() {
+        [Primitive::Ratio(x)] => Ok(Primitive::from(x.denom().clone())),
+        [Primitive::Integer(_) | Primitive::BigInteger(_)] => Ok(Primitive::Integer(1)),
+        _ => Err(OperationError::from("denominator expects one rational number")),
+    }
+}
+
+/// Division rounding towards zero.
+pub(crate) fn quot(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    divide_rounded("quot", args, |x, y| (x / y).trunc(), |x, y| (x / y).trunc())
+}
+
+/// The remainder of `quot`, which takes the sign of the dividend.
+pub(crate) fn rem(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    divide_rounded("rem", args, |x, y| x - y * (x / y).trunc(), |x, y| x % y)
+}
+
+/// The remainder of division rounding down, which takes the sign of the
+/// divisor.
+pub(crate) fn modulo(args: Vec<Primitive>) -> Result<Primitive, OperationError> {
+    divide_rounded("mod", args, |x, y| x - y * (x / y).floor(), |x, y| x - y * (x / y).floor())
+}
+
+/// Exact numbers are divided exactly, and anything involving a float is
+/// divided as floats.
+fn divide_rounded(
+    name: &str,
+    args: Vec<Primitive>,
+    exact_op: fn(&BigRational, &BigRational) -> BigRational,
+    float_op: fn(f64, f64) -> f64,
+) -> Result<Primitive, OperationError> {
+    let (x, y) = match args.as_slice() {
+        [x, y] => (x, y),
+        _ => return Err(OperationError::from(format!("{} expects two arguments", name).as_str())),
+    };
+    if !y.is_truthy() {
+        return Err(OperationError::from("Divide by zero"));
+    }
+    if is_exact(x) && is_exact(y) {
+        return Ok(Primitive::from(exact_op(&exact(x), &exact(y))));
+    }
+    match (as_f64(x), as_f64(y)) {
+        (Some(x), Some(y)) => Ok(Primitive::DoublePrecisionFloat(float_op(x, y))),
+        _ => Err(OperationError::from(format!("{} expects numbers, got {} and {}", name, x, y).as_str())),
+    }
+}
+
+fn fold_inverse(
+    name: &str,
+    args: Vec<Primitive>,
+    identity: Primitive,
+    op: fn(Primitive, Primitive) -> Result<Primitive, OperationError>,
+) -> Result<Primitive, OperationError> {
+    check_operands(name, &args)?;
+    let mut args = args.into_iter();
+    let first = args.next().ok_or_else(|| OperationError::from(format!("{} expects at least one argument", name).as_str()))?;
+    let rest: Vec<Primitive> = args.collect();
+    let (first, rest) = if rest.is_empty() { (identity, vec![first]) } else { (first, rest) };
+    rest.into_iter().try_fold(first, op)
+}
+
+/// Arithmetic is defined on numbers, strings and vectors of them.
+fn check_operands(name: &str, args: &[Primitive]) -> Result<(), OperationError> {
+    for arg in args {
+        match arg {
+            Primitive::Regex(_) => return Err(OperationError::from(format!("Cannot apply {} to {}", name, arg).as_str())),
+            Primitive::Vector(items) => check_operands(name, items)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn pairwise<F>(args: Vec<Primitive>, test: F) -> Result<Primitive, OperationError>
+where
+    F: Fn(&Primitive, &Primitive) -> Result<bool, OperationError>,
+{
+    let mut holds = true;
+    for pair in args.windows(2) {
+        holds &= test(&pair[0], &pair[1])?;
+    }
+    Ok(Primitive::Integer(holds as i64))
+}
+
+fn ordered(args: Vec<Primitive>, test: fn(Ordering) -> bool) -> Result<Primitive, OperationError> {
+    pairwise(args, |x, y| {
+        x.partial_cmp(y)
+            .map(test)
+            .ok_or_else(|| OperationError::from(format!("Cannot compare {} with {}", x, y).as_str()))
+    })
+}
+
+/// Numbers are equal across types, so `(== 1 1.0)` holds.
+fn equivalent(x: &Primitive, y: &Primitive) -> bool {
+    x == y || x.partial_cmp(y) == Some(Ordering::Equal)
+}
+
+fn power(x: Primitive, y: Primitive) -> Result<Primitive, OperationError> {
+    match (x, y) {
+        (x @ (Primitive::Integer(_) | Primitive::BigInteger(_)), Primitive::Integer(y)) if y >= 0 => {
+            let y = u32::try_from(y).map_err(|_| OperationError::from("Exponent too large"))?;
+            Ok(match x {
+                Primitive::Integer(x) => x.checked_pow(y).map(Primitive::Integer).unwrap_or_else(|| Primitive::from(BigInt::from(x).pow(y))),
+                Primitive::BigInteger(x) => Primitive::from(x.pow(y)),
+                _ => unreachable!("matched as an integer above"),
+            })
+        }
+        (Primitive::Ratio(x), Primitive::Integer(y)) => {
+            let y = i32::try_from(y).map_err(|_| OperationError::from("Exponent too large"))?;
+            Ok(Primitive::from(x.pow(y)))
+        }
+        // Everything else, including negative integer powers, is done with
+        // floats.
+        (x, y) => match (as_f64(&x), as_f64(&y)) {
+            (Some(x), Some(y)) => Ok(Primitive::DoublePrecisionFloat(x.powf(y))),
+            _ => Err(OperationError::from(format!("Cannot raise {} to {}", x, y).as_str())),
+        },
+    }
+}
+
+/// Bignums too large for a float become infinite.
+fn to_f64(x: &BigInt) -> f64 {
+    x.to_f64().unwrap_or(f64::NAN)
+}
+
+fn ratio_to_f64(x: &BigRational) -> f64 {
+    x.to_f64().unwrap_or(f64::NAN)
+}
+
+fn is_exact(x: &Primitive) -> bool {
+    matches!(x, Primitive::Integer(_) | Primitive::BigInteger(_) | Primitive::Ratio(_))
+}
+
+fn as_f64(x: &Primitive) -> Option<f64> {
+    match x {
+        Primitive::Integer(x) => Some(*x as f64),
+        Primitive::BigInteger(x) => Some(to_f64(x)),
+        Primitive::Ratio(x) => Some(ratio_to_f64(x)),
+        Primitive::DoublePrecisionFloat(x) => Some(*x),
+        _ => None,
+    }
+}
+
+/// An integer or ratio as a ratio.
+fn exact(x: &Primitive) -> BigRational {
+    match x {
+        Primitive::Integer(x) => BigRational::from_integer(BigInt::from(*x)),
+        Primitive::BigInteger(x) => BigRational::from_integer(x.clone()),
+        Primitive::Ratio(x) => x.clone(),
+        _ => unreachable!("only integers and ratios are exact"),
+    }
+}
+
+fn quotient(x: BigRational, y: BigRational) -> Result<Primitive, OperationError> {
+    if y.is_zero() {
+        return Err(OperationError::from("Divide by zero"));
+    }
+    Ok(Primitive::from(x / y))
+}
+
+fn unsupported(name: &str, x: &Primitive, y: &Primitive) -> OperationError {
+    OperationError::from(format!("Cannot apply {} to {} and {}", name, x, y).as_str())
+}
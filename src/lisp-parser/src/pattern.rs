@@ -0,0 +1,108 @@
+use std::fmt;
+use std::ops::Deref;
+
+use regex::{Captures, Regex};
+
+use crate::builtins::Builtin;
+use crate::native::mismatch;
+use crate::AstNode::{self, Terms, Value};
+use crate::{EvalError, Primitive};
+
+/// A compiled regular expression, written `#"..."`. Two patterns are equal
+/// when they were written the same way.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    regex: Regex,
+    // The same pattern anchored at both ends, for `re-matches`.
+    whole: Regex,
+}
+
+impl Pattern {
+    pub fn new(source: &str) -> Result<Pattern, regex::Error> {
+        let regex = Regex::new(source)?;
+        let whole = Regex::new(&format!("^(?:{})$", source))?;
+        Ok(Pattern { regex, whole })
+    }
+}
+
+impl Deref for Pattern {
+    type Target = Regex;
+
+    fn deref(&self) -> &Regex {
+        &self.regex
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Pattern) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+/// Prints as the literal it was read from.
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#\"{}\"", self.as_str())
+    }
+}
+
+pub(crate) const REGEX_BUILTINS: &[(&str, Builtin)] = &[
+    ("re-find", re_find),
+    ("re-matches", re_matches),
+    ("re-seq", re_seq),
+    ("re-replace", re_replace),
+];
+
+// Like Clojure, a match is returned as a string when the pattern has no
+// groups, and otherwise as a list of the whole match followed by each group.
+// No match, and groups that didn't take part in the match, are the empty
+// list.
+
+/// `(re-find re s)` finds the first match anywhere in `s`.
+fn re_find(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    let (pattern, s) = pattern_and_text("re-find", args)?;
+    Ok(pattern.captures(s).map(|captures| found(&captures)).unwrap_or(Terms(vec![])))
+}
+
+/// `(re-matches re s)` only matches when the whole of `s` matches.
+fn re_matches(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    let (pattern, s) = pattern_and_text("re-matches", args)?;
+    Ok(pattern.whole.captures(s).map(|captures| found(&captures)).unwrap_or(Terms(vec![])))
+}
+
+/// `(re-seq re s)` lists every match.
+fn re_seq(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    let (pattern, s) = pattern_and_text("re-seq", args)?;
+    Ok(Terms(pattern.captures_iter(s).map(|captures| found(&captures)).collect()))
+}
+
+/// `(re-replace re s replacement)` replaces every match. The replacement can
+/// refer to groups as `$1` or `${name}`.
+fn re_replace(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    if args.len() != 3 {
+        return Err(EvalError::Arity { name: "re-replace".to_string(), min: 3, max: 3, given: args.len() });
+    }
+    let (pattern, s) = pattern_and_text("re-replace", &args[..2])?;
+    let replacement = match &args[2] {
+        Value(Primitive::Str(replacement)) => replacement,
+        other => return Err(mismatch("a string", other)),
+    };
+    Ok(Value(Primitive::Str(pattern.replace_all(s, replacement.as_str()).into_owned())))
+}
+
+fn pattern_and_text<'a>(name: &str, args: &'a [AstNode]) -> Result<(&'a Pattern, &'a str), EvalError> {
+    match args {
+        [Value(Primitive::Regex(pattern)), Value(Primitive::Str(s))] => Ok((pattern, s)),
+        [Value(Primitive::Regex(_)), other] => Err(mismatch("a string", other)),
+        [other, _] => Err(mismatch("a regex", other)),
+        _ => Err(EvalError::Arity { name: name.to_string(), min: 2, max: 2, given: args.len() }),
+    }
+}
+
+fn found(captures: &Captures) -> AstNode {
+    let text = |m: regex::Match| Value(Primitive::Str(m.as_str().to_string()));
+    match captures.len() {
+        1 => text(captures.get(0).unwrap()),
+        _ => Terms(captures.iter().map(|m| m.map(text).unwrap_or(Terms(vec![]))).collect()),
+    }
+}
@@ -0,0 +1,492 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+
+use crate::builtins::arity;
+use crate::lazy::{generate, LazySeq, Walk};
+use crate::native::{Apply, HigherOrder};
+use crate::AstNode::{self, Terms, Value};
+use crate::{interrupt, is_truthy, operators, EvalError, Primitive};
+
+/// Sequence functions. They take lists, vectors, strings (as a sequence of
+/// one-character strings) and lazy sequences. Given a lazy sequence, `map`,
+/// `filter`, `take`, `drop`, `rest`, `cons`, `concat` and `zip` return one
+/// too, and the rest return lists.
+pub(crate) const SEQUENCE_BUILTINS: &[(&str, HigherOrder)] = &[
+    ("range", range),
+    ("iterate", iterate),
+    ("repeat", repeat),
+    ("cycle", cycle),
+    ("take", take),
+    ("drop", drop),
+    ("first", first),
+    ("rest", rest),
+    ("cons", cons),
+    ("concat", concat),
+    ("reverse", reverse),
+    ("frequencies", frequencies),
+    ("zip", zip),
+    ("map", map),
+    ("filter", filter),
+    ("reduce", reduce),
+    ("sort", sort),
+    ("sort-by", sort_by),
+    ("group-by", group_by),
+];
+
+/// `(range end)`, `(range start end)` or `(range start end step)`, counting
+/// up to but not including `end`. `(range)` counts up from 0 forever. Ranges
+/// are lazy, so only the items that are used are made.
+fn range(args: &[AstNode], _: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("range", args, 0, 3)?;
+    if args.is_empty() {
+        return Ok(generate(Primitive::Integer(0), |n, _| {
+            let next = operators::add(vec![n.clone(), Primitive::Integer(1)])?;
+            Ok(Some((Value(n), next)))
+        }));
+    }
+    if let Ok(bounds) = args.iter().map(i64::try_from).collect::<Result<Vec<_>, _>>() {
+        let (start, end, step) = bounds_of(&bounds, 0, 1)?;
+        // The next item is `None` once counting on would overflow.
+        return Ok(generate((Some(start), end, step), |(x, end, step), _| match x {
+            Some(x) if (step > 0 && x < end) || (step < 0 && x > end) => {
+                Ok(Some((Value(Primitive::Integer(x)), (x.checked_add(step), end, step))))
+            }
+            _ => Ok(None),
+        }));
+    }
+    let bounds = args.iter().map(f64::try_from).collect::<Result<Vec<_>, _>>()?;
+    let (start, end, step) = bounds_of(&bounds, 0.0, 1.0)?;
+    Ok(generate((start, end, step), |(x, end, step), _| {
+        if (step > 0.0 && x < end) || (step < 0.0 && x > end) {
+            return Ok(Some((Value(Primitive::DoublePrecisionFloat(x)), (x + step, end, step))));
+        }
+        Ok(None)
+    }))
+}
+
+fn bounds_of<T: Copy + PartialEq>(bounds: &[T], zero: T, one: T) -> Result<(T, T, T), EvalError> {
+    let (start, end, step) = match *bounds {
+        [end] => (zero, end, one),
+        [start, end] => (start, end, one),
+        [start, end, step] => (start, end, step),
+        _ => unreachable!("arity checked by range"),
+    };
+    if step == zero {
+        return Err(EvalError::InvalidForm("range needs a non-zero step".to_string()));
+    }
+    Ok((start, end, step))
+}
+
+/// `(iterate f x)` is the infinite sequence `x`, `(f x)`, `(f (f x))` and
+/// so on.
+fn iterate(args: &[AstNode], _: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("iterate", args, 2, 2)?;
+    // `f` is only called once the item after `x` is wanted.
+    Ok(generate((args[0].clone(), args[1].clone(), false), |(f, x, started), apply| {
+        let x = if started { apply(&f, vec![x])? } else { x };
+        Ok(Some((x.clone(), (f, x, true))))
+    }))
+}
+
+/// `(repeat x)` is `x` forever, and `(repeat n x)` is `n` of them. Both are
+/// lazy.
+fn repeat(args: &[AstNode], _: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("repeat", args, 1, 2)?;
+    match args {
+        [x] => Ok(generate(x.clone(), |x, _| Ok(Some((x.clone(), x))))),
+        [n, x] => Ok(generate((count(n)?, x.clone()), |(n, x), _| match n {
+            0 => Ok(None),
+            n => Ok(Some((x.clone(), (n - 1, x)))),
+        })),
+        _ => unreachable!("arity checked above"),
+    }
+}
+
+/// `(cycle coll)` repeats the items of `coll` forever.
+fn cycle(args: &[AstNode], _: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("cycle", args, 1, 1)?;
+    let walk = Walk::new(&args[0])?;
+    Ok(generate((args[0].clone(), walk), |(coll, mut walk), apply| {
+        if let Some(item) = walk.next(apply)? {
+            return Ok(Some((item, (coll, walk))));
+        }
+        let mut walk = Walk::new(&coll)?;
+        Ok(walk.next(apply)?.map(|item| (item, (coll, walk))))
+    }))
+}
+
+/// `(take n coll)` is the first `n` items.
+fn take(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("take", args, 2, 2)?;
+    let n = count(&args[0])?;
+    let mut walk = Walk::new(&args[1])?;
+    if is_lazy(&args[1]) {
+        return Ok(generate((n, walk), |(n, mut walk), apply| match n {
+            0 => Ok(None),
+            _ => Ok(walk.next(apply)?.map(|item| (item, (n - 1, walk)))),
+        }));
+    }
+    let mut result = vec![];
+    while result.len() < n {
+        interrupt::check()?;
+        match walk.next(apply)? {
+            Some(item) => result.push(item),
+            None => break,
+        }
+    }
+    Ok(Terms(result))
+}
+
+/// `(drop n coll)` is everything after the first `n` items.
+fn drop(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("drop", args, 2, 2)?;
+    let n = count(&args[0])?;
+    let skip = move |coll: &AstNode, apply: &mut Apply| {
+        let mut walk = Walk::new(coll)?;
+        for _ in 0..n {
+            interrupt::check()?;
+            if walk.next(apply)?.is_none() {
+                break;
+            }
+        }
+        Ok(walk.into_seq())
+    };
+    if is_lazy(&args[1]) {
+        let coll = args[1].clone();
+        return Ok(AstNode::LazySeq(LazySeq::new(move |apply| skip(&coll, apply))));
+    }
+    skip(&args[1], apply)
+}
+
+/// The first item, or the empty list when there isn't one.
+fn first(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("first", args, 1, 1)?;
+    Ok(Walk::new(&args[0])?.next(apply)?.unwrap_or(Terms(vec![])))
+}
+
+fn rest(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("rest", args, 1, 1)?;
+    let mut walk = Walk::new(&args[0])?;
+    walk.next(apply)?;
+    Ok(walk.into_seq())
+}
+
+/// `(cons x coll)` puts `x` in front of `coll`.
+fn cons(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("cons", args, 2, 2)?;
+    if is_lazy(&args[1]) {
+        return Ok(AstNode::LazySeq(LazySeq::cons(args[0].clone(), args[1].clone())));
+    }
+    let mut result = vec![args[0].clone()];
+    result.extend(items(&args[1], apply)?);
+    Ok(Terms(result))
+}
+
+fn concat(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    if args.iter().any(is_lazy) {
+        let walks = args.iter().map(Walk::new).collect::<Result<Vec<_>, _>>()?;
+        return Ok(generate(walks, |mut walks, apply| {
+            while !walks.is_empty() {
+                if let Some(item) = walks[0].next(apply)? {
+                    return Ok(Some((item, walks)));
+                }
+                walks.remove(0);
+            }
+            Ok(None)
+        }));
+    }
+    let mut result = vec![];
+    for arg in args {
+        result.extend(items(arg, apply)?);
+    }
+    Ok(Terms(result))
+}
+
+fn reverse(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("reverse", args, 1, 1)?;
+    Ok(Terms(items(&args[0], apply)?.into_iter().rev().collect()))
+}
+
+/// A list of `(item count)` pairs, in the order each item first appears.
+fn frequencies(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("frequencies", args, 1, 1)?;
+    let mut counts = Groups::new();
+    for item in items(&args[0], apply)? {
+        interrupt::check()?;
+        *counts.entry(item, || 0) += 1;
+    }
+    Ok(Terms(counts.entries.into_iter().map(|(item, count)| Terms(vec![item, Value(Primitive::Integer(count))])).collect()))
+}
+
+/// `(zip xs ys ...)` pairs up the items of each collection, stopping at the
+/// end of the shortest.
+fn zip(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("zip", args, 1, usize::MAX)?;
+    if args.iter().any(is_lazy) {
+        let walks = args.iter().map(Walk::new).collect::<Result<Vec<_>, _>>()?;
+        return Ok(generate(walks, |mut walks, apply| Ok(next_row(&mut walks, apply)?.map(|row| (Terms(row), walks)))));
+    }
+    Ok(Terms(transpose(args, apply)?.into_iter().map(Terms).collect()))
+}
+
+/// `(map f coll)` applies `f` to every item. Given several collections, `f`
+/// takes one argument from each, and stops at the end of the shortest.
+fn map(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("map", args, 2, usize::MAX)?;
+    if args[1..].iter().any(is_lazy) {
+        let walks = args[1..].iter().map(Walk::new).collect::<Result<Vec<_>, _>>()?;
+        return Ok(generate((args[0].clone(), walks), |(f, mut walks), apply| match next_row(&mut walks, apply)? {
+            Some(row) => Ok(Some((apply(&f, row)?, (f, walks)))),
+            None => Ok(None),
+        }));
+    }
+    let result = transpose(&args[1..], apply)?.into_iter().map(|row| apply(&args[0], row)).collect::<Result<_, _>>()?;
+    Ok(Terms(result))
+}
+
+/// `(filter pred coll)` keeps the items for which `pred` is truthy.
+fn filter(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("filter", args, 2, 2)?;
+    let mut walk = Walk::new(&args[1])?;
+    if is_lazy(&args[1]) {
+        return Ok(generate((args[0].clone(), walk), |(pred, mut walk), apply| {
+            while let Some(item) = walk.next(apply)? {
+                interrupt::check()?;
+                if is_truthy(&apply(&pred, vec![item.clone()])?) {
+                    return Ok(Some((item, (pred, walk))));
+                }
+            }
+            Ok(None)
+        }));
+    }
+    let mut result = vec![];
+    while let Some(item) = walk.next(apply)? {
+        interrupt::check()?;
+        if is_truthy(&apply(&args[0], vec![item.clone()])?) {
+            result.push(item);
+        }
+    }
+    Ok(Terms(result))
+}
+
+/// `(reduce f coll)` or `(reduce f init coll)`. Without `init`, the first
+/// item is used, and an empty collection gives `(f)`.
+fn reduce(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("reduce", args, 2, 3)?;
+    let (mut accumulator, mut walk) = match args {
+        [_, coll] => {
+            let mut walk = Walk::new(coll)?;
+            match walk.next(apply)? {
+                Some(first) => (first, walk),
+                None => return apply(&args[0], vec![]),
+            }
+        }
+        [_, init, coll] => (init.clone(), Walk::new(coll)?),
+        _ => unreachable!("arity checked above"),
+    };
+    while let Some(item) = walk.next(apply)? {
+        interrupt::check()?;
+        accumulator = apply(&args[0], vec![accumulator, item])?;
+    }
+    Ok(accumulator)
+}
+
+/// `(sort coll)` or `(sort before? coll)`, where `(before? x y)` is truthy
+/// when `x` belongs before `y`, as with `<` or `>`. The sort is stable.
+fn sort(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("sort", args, 1, 2)?;
+    let coll = items(&args[args.len() - 1], apply)?;
+    let sorted = match args {
+        [_] => merge_sort(coll, &mut |x, y| Ok(compare(x, y)? == Ordering::Less))?,
+        [before, _] => merge_sort(coll, &mut |x, y| Ok(is_truthy(&apply(before, vec![x.clone(), y.clone()])?)))?,
+        _ => unreachable!("arity checked above"),
+    };
+    Ok(Terms(sorted))
+}
+
+/// `(sort-by key coll)` or `(sort-by key before? coll)` sorts by `(key item)`.
+fn sort_by(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("sort-by", args, 2, 3)?;
+    let keyed = items(&args[args.len() - 1], apply)?
+        .into_iter()
+        .map(|item| Ok((apply(&args[0], vec![item.clone()])?, item)))
+        .collect::<Result<Vec<_>, EvalError>>()?;
+    let sorted = match args {
+        [_, _] => merge_sort(keyed, &mut |(x, _), (y, _)| Ok(compare(x, y)? == Ordering::Less))?,
+        [_, before, _] => merge_sort(keyed, &mut |(x, _), (y, _)| Ok(is_truthy(&apply(before, vec![x.clone(), y.clone()])?)))?,
+        _ => unreachable!("arity checked above"),
+    };
+    Ok(Terms(sorted.into_iter().map(|(_, item)| item).collect()))
+}
+
+/// `(group-by f coll)` is a list of `(key items)` pairs, where `items` are
+/// those for which `(f item)` is `key`, in the order each key first appears.
+fn group_by(args: &[AstNode], apply: &mut Apply) -> Result<AstNode, EvalError> {
+    arity("group-by", args, 2, 2)?;
+    let mut groups = Groups::new();
+    for item in items(&args[1], apply)? {
+        interrupt::check()?;
+        let key = apply(&args[0], vec![item.clone()])?;
+        groups.entry(key, Vec::new).push(item);
+    }
+    Ok(Terms(groups.entries.into_iter().map(|(key, group)| Terms(vec![key, Terms(group)])).collect()))
+}
+
+/// Values paired with something each, in the order each value first
+/// appears, for `frequencies` and `group-by`. Values are looked up by
+/// `Key`, and the few without one, such as functions, by comparing them
+/// with each other.
+struct Groups<T> {
+    entries: Vec<(AstNode, T)>,
+    hashed: HashMap<Key, usize>,
+    unhashed: Vec<usize>,
+}
+
+impl<T> Groups<T> {
+    fn new() -> Groups<T> {
+        Groups { entries: vec![], hashed: HashMap::new(), unhashed: vec![] }
+    }
+
+    /// What goes with `value`, starting from `init()` the first time.
+    fn entry(&mut self, value: AstNode, init: impl FnOnce() -> T) -> &mut T {
+        let entries = &mut self.entries;
+        let index = match Key::of(&value) {
+            Some(key) => *self.hashed.entry(key).or_insert_with(|| {
+                entries.push((value, init()));
+                entries.len() - 1
+            }),
+            None => match self.unhashed.iter().find(|&&index| entries[index].0 == value) {
+                Some(&index) => index,
+                None => {
+                    entries.push((value, init()));
+                    self.unhashed.push(entries.len() - 1);
+                    entries.len() - 1
+                }
+            },
+        };
+        &mut entries[index].1
+    }
+}
+
+/// A hashable stand-in for a value, equal exactly when the values are.
+#[derive(PartialEq, Eq, Hash)]
+enum Key {
+    Integer(i64),
+    BigInteger(BigInt),
+    Ratio(BigRational),
+    Float(u64),
+    Str(String),
+    Symbol(String),
+    Vector(Vec<Key>),
+    List(Vec<Key>),
+}
+
+impl Key {
+    fn of(node: &AstNode) -> Option<Key> {
+        match node {
+            Value(value) => Key::of_primitive(value),
+            Terms(items) => items.iter().map(Key::of).collect::<Option<_>>().map(Key::List),
+            AstNode::Ident(name) => Some(Key::Symbol(name.clone())),
+            _ => None,
+        }
+    }
+
+    fn of_primitive(value: &Primitive) -> Option<Key> {
+        match value {
+            Primitive::Integer(x) => Some(Key::Integer(*x)),
+            Primitive::BigInteger(x) => Some(Key::BigInteger(x.clone())),
+            Primitive::Ratio(x) => Some(Key::Ratio(x.clone())),
+            // NaN equals nothing, and adding 0.0 makes -0.0 the same as 0.0.
+            Primitive::DoublePrecisionFloat(x) if x.is_nan() => None,
+            Primitive::DoublePrecisionFloat(x) => Some(Key::Float((x + 0.0).to_bits())),
+            Primitive::Str(s) => Some(Key::Str(s.clone())),
+            Primitive::Vector(items) => items.iter().map(Key::of_primitive).collect::<Option<_>>().map(Key::Vector),
+            _ => None,
+        }
+    }
+}
+
+fn is_lazy(node: &AstNode) -> bool {
+    matches!(node, AstNode::LazySeq(_))
+}
+
+fn items(node: &AstNode, apply: &mut Apply) -> Result<Vec<AstNode>, EvalError> {
+    Walk::new(node)?.collect(apply)
+}
+
+/// One item from each sequence, or `None` once any of them runs out.
+fn next_row(walks: &mut [Walk], apply: &mut Apply) -> Result<Option<Vec<AstNode>>, EvalError> {
+    let mut row = vec![];
+    for walk in walks {
+        match walk.next(apply)? {
+            Some(item) => row.push(item),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(row))
+}
+
+/// Turns several collections into rows of one item from each, as long as
+/// the shortest.
+fn transpose(colls: &[AstNode], apply: &mut Apply) -> Result<Vec<Vec<AstNode>>, EvalError> {
+    let mut walks = colls.iter().map(Walk::new).collect::<Result<Vec<_>, _>>()?;
+    let mut rows = vec![];
+    while let Some(row) = next_row(&mut walks, apply)? {
+        interrupt::check()?;
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// A count for `take`, `drop` and `repeat`. Negative counts are treated as
+/// zero.
+fn count(node: &AstNode) -> Result<usize, EvalError> {
+    Ok(i64::try_from(node)?.max(0) as usize)
+}
+
+/// The natural order: numbers by value, strings alphabetically and lists
+/// item by item.
+fn compare(x: &AstNode, y: &AstNode) -> Result<Ordering, EvalError> {
+    let ordering = match (x, y) {
+        (Value(a), Value(b)) => a.partial_cmp(b),
+        (Terms(a), Terms(b)) => {
+            for (a, b) in a.iter().zip(b) {
+                match compare(a, b)? {
+                    Ordering::Equal => continue,
+                    ordering => return Ok(ordering),
+                }
+            }
+            Some(a.len().cmp(&b.len()))
+        }
+        _ => None,
+    };
+    ordering.ok_or_else(|| EvalError::InvalidForm(format!("Cannot compare {} and {}", x, y)))
+}
+
+/// A stable merge sort. Unlike `slice::sort_by` it can stop at the first
+/// error, and it doesn't mind a comparison that isn't a total order.
+fn merge_sort<T>(
+    mut items: Vec<T>,
+    before: &mut dyn FnMut(&T, &T) -> Result<bool, EvalError>,
+) -> Result<Vec<T>, EvalError> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+    let right = items.split_off(items.len() / 2);
+    let mut left = merge_sort(items, before)?.into_iter().peekable();
+    let mut right = merge_sort(right, before)?.into_iter().peekable();
+    let mut merged = vec![];
+    while let (Some(x), Some(y)) = (left.peek(), right.peek()) {
+        interrupt::check()?;
+        // Taking from the left unless the right is strictly before it keeps
+        // equal items in their original order.
+        let next = if before(y, x)? { right.next() } else { left.next() };
+        merged.extend(next);
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
@@ -0,0 +1,125 @@
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::AstNode::{self, *};
+use crate::{EvalError, Primitive};
+
+/// The nesting depth `evaluate` allows before failing with
+/// `EvalError::StackOverflow`. Evaluation also fails with that error when the
+/// thread is about to run out of native stack, so the limit is safe on any
+/// thread, but each level costs a few kilobytes and only a large stack (the
+/// REPL uses 512MB) can go this deep.
+pub const DEFAULT_MAX_DEPTH: usize = 10_000;
+
+/// The native stack that must be left for evaluation to go a level deeper.
+/// A level takes a few kilobytes in release builds and up to about 20KB in
+/// debug builds, and an error raised this deep may still have to drop the
+/// rest of a form nested as deeply as the reader allows.
+pub(crate) const RED_ZONE: usize = 256 * 1024;
+
+static MAX_DEPTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_DEPTH);
+
+thread_local! {
+    // One entry per nested evaluation, naming the function or loop whose
+    // body it is evaluating, if any.
+    static CALL_STACK: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+}
+
+pub fn set_max_depth(depth: usize) {
+    MAX_DEPTH.store(depth, Ordering::Relaxed);
+}
+
+pub fn max_depth() -> usize {
+    MAX_DEPTH.load(Ordering::Relaxed)
+}
+
+/// Marks one level of native recursion in the evaluator. The level is
+/// released when the frame is dropped, including on early error returns.
+pub(crate) struct EvalFrame;
+
+impl EvalFrame {
+    pub(crate) fn enter() -> Result<EvalFrame, EvalError> {
+        CALL_STACK.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            let out_of_stack = stacker::remaining_stack().is_some_and(|remaining| remaining < RED_ZONE);
+            if stack.len() >= max_depth() || out_of_stack {
+                let chain = stack.iter().filter(|name| !name.is_empty()).cloned().collect();
+                return Err(EvalError::StackOverflow { depth: stack.len(), chain });
+            }
+            stack.push(String::new());
+            Ok(EvalFrame)
+        })
+    }
+
+    /// Records that this level is now evaluating the body of `name`. Tail
+    /// calls reuse the level, so this replaces the previous name.
+    pub(crate) fn name(&self, name: &str) {
+        CALL_STACK.with(|stack| {
+            if let Some(top) = stack.borrow_mut().last_mut() {
+                top.clear();
+                top.push_str(name);
+            }
+        })
+    }
+}
+
+impl Drop for EvalFrame {
+    fn drop(&mut self) {
+        CALL_STACK.with(|stack| {
+            stack.borrow_mut().pop();
+        })
+    }
+}
+
+/// How deeply lists and vectors may nest in a value. Copying, comparing,
+/// printing and dropping a value recurse once per level.
+pub(crate) const MAX_VALUE_NESTING: usize = 1_000;
+
+/// Fails if `value` nests lists or vectors deeper than `MAX_VALUE_NESTING`.
+/// Lazy sequences and closures are shared rather than copied and are taken
+/// apart by `dismantle`, so they aren't looked into.
+pub(crate) fn check_nesting(value: AstNode) -> Result<AstNode, EvalError> {
+    enum Node<'a> {
+        Form(&'a AstNode),
+        Value(&'a Primitive),
+    }
+    let mut pending = vec![(Node::Form(&value), 0)];
+    while let Some((node, depth)) = pending.pop() {
+        if depth > MAX_VALUE_NESTING {
+            let message = format!("Values can't be nested more than {} levels deep", MAX_VALUE_NESTING);
+            return Err(EvalError::InvalidForm(message));
+        }
+        match node {
+            Node::Form(Terms(items) | Vector(items)) => pending.extend(items.iter().map(|item| (Node::Form(item), depth + 1))),
+            Node::Form(Quote(form) | Quasiquote(form) | Unquote(form) | UnquoteSplicing(form)) => {
+                pending.push((Node::Form(form), depth + 1))
+            }
+            Node::Form(IsGlobal { expr, .. }) => pending.push((Node::Form(expr), depth + 1)),
+            Node::Form(AstNode::Value(Primitive::Vector(items))) | Node::Value(Primitive::Vector(items)) => {
+                pending.extend(items.iter().map(|item| (Node::Value(item), depth + 1)))
+            }
+            _ => {}
+        }
+    }
+    Ok(value)
+}
+
+/// Drops `nodes` without recursing into them. A closure holds the scope it
+/// was made in and a lazy sequence holds its items, either of which can
+/// lead on to another, so a long chain would otherwise be dropped with one
+/// native call per link.
+pub(crate) fn dismantle(mut pending: Vec<AstNode>) {
+    while let Some(node) = pending.pop() {
+        match node {
+            Terms(items) | Vector(items) => pending.extend(items),
+            Function { mut closure, .. } => {
+                if let Some(bindings) = Arc::get_mut(&mut closure) {
+                    pending.extend(bindings.drain().map(|(_, value)| value));
+                }
+            }
+            LazySeq(mut seq) => seq.take_parts(&mut pending),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::iter;
+
+use crate::builtins::{arity, Builtin};
+use crate::native::mismatch;
+use crate::AstNode::{self, Terms, Value};
+use crate::{EvalError, Primitive};
+
+/// String functions, registered as builtins. Indices count characters, not
+/// bytes.
+pub(crate) const STRING_BUILTINS: &[(&str, Builtin)] = &[
+    ("str", stringify),
+    ("subs", subs),
+    ("split", split),
+    ("join", join),
+    ("trim", trim),
+    ("upper-case", upper_case),
+    ("lower-case", lower_case),
+    ("starts-with?", starts_with),
+    ("includes?", includes),
+    ("replace", replace),
+    ("index-of", index_of),
+    ("format", format),
+];
+
+/// Concatenates its arguments. Strings are included as they are, without
+/// the quotes they print with.
+fn stringify(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    Ok(string(args.iter().map(to_text).collect()))
+}
+
+/// `(subs s start)` or `(subs s start end)`.
+fn subs(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("subs", args, 2, 3)?;
+    let s = text(&args[0])?;
+    let length = s.chars().count();
+    let start = index(&args[1])?;
+    let end = match args.get(2) {
+        Some(end) => index(end)?,
+        None => length,
+    };
+    if start > end || end > length {
+        return Err(EvalError::InvalidForm(format!("String index out of range: {} to {} of {}", start, end, length)));
+    }
+    Ok(string(s.chars().skip(start).take(end - start).collect()))
+}
+
+/// `(split s separator)` returns a list of the pieces. The separator may be
+/// a string or a regex.
+fn split(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("split", args, 2, 2)?;
+    let s = text(&args[0])?;
+    let pieces: Vec<&str> = match &args[1] {
+        Value(Primitive::Regex(pattern)) => pattern.split(s).collect(),
+        separator => s.split(text(separator)?).collect(),
+    };
+    Ok(Terms(pieces.into_iter().map(|piece| string(piece.to_string())).collect()))
+}
+
+/// `(join items)` or `(join separator items)`.
+fn join(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("join", args, 1, 2)?;
+    let (separator, items) = match args {
+        [items] => ("", items),
+        [separator, items] => (text(separator)?, items),
+        _ => unreachable!("arity checked above"),
+    };
+    let items = collection(items)?;
+    Ok(string(items.iter().map(to_text).collect::<Vec<_>>().join(separator)))
+}
+
+fn trim(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("trim", args, 1, 1)?;
+    Ok(string(text(&args[0])?.trim().to_string()))
+}
+
+fn upper_case(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("upper-case", args, 1, 1)?;
+    Ok(string(text(&args[0])?.to_uppercase()))
+}
+
+fn lower_case(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("lower-case", args, 1, 1)?;
+    Ok(string(text(&args[0])?.to_lowercase()))
+}
+
+fn starts_with(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("starts-with?", args, 2, 2)?;
+    Ok(boolean(text(&args[0])?.starts_with(text(&args[1])?)))
+}
+
+fn includes(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("includes?", args, 2, 2)?;
+    Ok(boolean(text(&args[0])?.contains(text(&args[1])?)))
+}
+
+/// `(replace s match replacement)` replaces every occurrence of `match`,
+/// which may be a string or a regex.
+fn replace(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("replace", args, 3, 3)?;
+    let s = text(&args[0])?;
+    let replacement = text(&args[2])?;
+    match &args[1] {
+        Value(Primitive::Regex(pattern)) => Ok(string(pattern.replace_all(s, replacement).into_owned())),
+        target => Ok(string(s.replace(text(target)?, replacement))),
+    }
+}
+
+/// `(index-of s value)` or `(index-of s value from)`. Returns -1 when
+/// `value` isn't found.
+fn index_of(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("index-of", args, 2, 3)?;
+    let s = text(&args[0])?;
+    let value = text(&args[1])?;
+    let from = match args.get(2) {
+        Some(from) => index(from)?,
+        None => 0,
+    };
+    let found = byte_offset(s, from).and_then(|offset| {
+        let position = s[offset..].find(value)?;
+        Some(s[..offset + position].chars().count() as i64)
+    });
+    Ok(Value(Primitive::Integer(found.unwrap_or(-1))))
+}
+
+/// A small `printf`: `%s` inserts any value as `str` would, `%d` an integer,
+/// `%f` or `%.2f` a number, `%x` an integer in hex, and `%%` a percent sign.
+fn format(args: &[AstNode]) -> Result<AstNode, EvalError> {
+    arity("format", args, 1, usize::MAX)?;
+    let template = text(&args[0])?;
+    let mut values = args[1..].iter();
+    let mut next = |directive: &str| {
+        values
+            .next()
+            .ok_or_else(|| EvalError::InvalidForm(format!("format has no argument left for %{}", directive)))
+    };
+    let mut result = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        let mut precision = String::new();
+        if chars.peek() == Some(&'.') {
+            chars.next();
+            while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                precision.push(digit);
+            }
+        }
+        match chars.next() {
+            Some('%') => result.push('%'),
+            Some('s') => result.push_str(&to_text(next("s")?)),
+            Some('d') => match next("d")? {
+                Value(Primitive::Integer(n)) => result.push_str(&n.to_string()),
+                Value(Primitive::BigInteger(n)) => result.push_str(&n.to_string()),
+                other => return Err(mismatch("an integer", other)),
+            },
+            Some('x') => match next("x")? {
+                Value(Primitive::Integer(n)) => result.push_str(&format!("{:x}", n)),
+                Value(Primitive::BigInteger(n)) => result.push_str(&n.to_str_radix(16)),
+                other => return Err(mismatch("an integer", other)),
+            },
+            Some('f') => {
+                let n = f64::try_from(next("f")?)?;
+                let precision = precision.parse().unwrap_or(6);
+                result.push_str(&format!("{:.*}", precision, n));
+            }
+            other => {
+                let directive = other.map(String::from).unwrap_or_default();
+                return Err(EvalError::InvalidForm(format!("Unknown format directive %{}{}", precision, directive)));
+            }
+        }
+    }
+    Ok(string(result))
+}
+
+fn text(node: &AstNode) -> Result<&str, EvalError> {
+    match node {
+        Value(Primitive::Str(s)) => Ok(s),
+        other => Err(mismatch("a string", other)),
+    }
+}
+
+fn index(node: &AstNode) -> Result<usize, EvalError> {
+    match node {
+        Value(Primitive::Integer(i)) if *i >= 0 => Ok(*i as usize),
+        other => Err(mismatch("a non-negative index", other)),
+    }
+}
+
+fn collection(node: &AstNode) -> Result<Vec<AstNode>, EvalError> {
+    match node {
+        Terms(items) => Ok(items.clone()),
+        Value(Primitive::Vector(items)) => Ok(items.iter().cloned().map(Value).collect()),
+        Value(Primitive::Array(array)) => Ok(array.rows().into_iter().map(Value).collect()),
+        other => Err(mismatch("a list", other)),
+    }
+}
+
+fn to_text(node: &AstNode) -> String {
+    match node {
+        Value(Primitive::Str(s)) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// The byte offset of the character at `index`, which may be one past the
+/// last character.
+fn byte_offset(s: &str, index: usize) -> Option<usize> {
+    s.char_indices().map(|(offset, _)| offset).chain(iter::once(s.len())).nth(index)
+}
+
+fn string(s: String) -> AstNode {
+    Value(Primitive::Str(s))
+}
+
+fn boolean(value: bool) -> AstNode {
+    Value(Primitive::Integer(value as i64))
+}
@@ -0,0 +1,25 @@
+mod common;
+use common::show;
+
+#[test]
+fn insert_puts_a_verb_between_items_grouping_from_the_right() {
+    assert_eq!(show("(+/ 1 2 3)"), "6");
+    assert_eq!(show("(+/ [1 2 3])"), "6");
+    assert_eq!(show("(-/ 1 2 3)"), "2");
+    assert_eq!(show("(+/ (i. 2 3))"), "[3, 5, 7]");
+    assert_eq!(show("(+/ [])"), "0");
+    assert_eq!(show("(*/ [])"), "1");
+}
+
+#[test]
+fn scan_inserts_into_every_prefix() {
+    assert_eq!(show("(+\\ 1 2 3)"), "[1, 3, 6]");
+    assert_eq!(show("(defn max [x y] (if (> x y) x y))\n(max\\ [1 3 2 5])"), "[1, 3, 3, 5]");
+}
+
+#[test]
+fn insert_between_two_lists_makes_a_table() {
+    assert_eq!(show("(*/ [1 2 3] [1 2])"), "[[1, 2], [2, 4], [3, 6]]");
+    assert_eq!(show("(outer + [1 2] [10 20])"), "[[11, 21], [12, 22]]");
+    assert_eq!(show("(insert +)"), "#<native-fn +/>");
+}
@@ -0,0 +1,50 @@
+use lisp_parser::{Array, Primitive};
+
+mod common;
+use common::show;
+
+#[test]
+fn counts_from_zero_in_any_shape() {
+    assert_eq!(show("(i. 5)"), "[0, 1, 2, 3, 4]");
+    assert_eq!(show("(i. 2 3)"), "[[0, 1, 2], [3, 4, 5]]");
+    assert_eq!(show("(iota [2 1 2])"), "[[[0, 1]], [[2, 3]]]");
+    assert_eq!(show("(i. 0 3)"), "[]");
+}
+
+#[test]
+fn reshapes_repeating_items_as_needed() {
+    assert_eq!(show("($ [2 2] [1 2 3])"), "[[1, 2], [3, 1]]");
+    assert_eq!(show("(reshape [3] (i. 2 2))"), "[0, 1, 2]");
+    assert_eq!(show("(reshape [0] [])"), "[]");
+    assert_eq!(show("(reshape [2 2] [])"), "error: Cannot reshape an empty array");
+}
+
+#[test]
+fn gives_the_length_of_each_axis() {
+    assert_eq!(show("($ (i. 2 3))"), "[2, 3]");
+    assert_eq!(show("(shape [1 2 3])"), "[3]");
+    assert_eq!(show("($ 5)"), "[]");
+    assert_eq!(show("(shape [[1 2] [3]])"), "error: shape expects a rectangular array, got [[1, 2], [3]]");
+}
+
+#[test]
+fn transposes_by_reversing_the_axes() {
+    assert_eq!(show("(transpose (i. 2 3))"), "[[0, 3], [1, 4], [2, 5]]");
+    assert_eq!(show("($ (transpose (i. 2 3 4)))"), "[4, 3, 2]");
+}
+
+#[test]
+fn arrays_work_with_arithmetic_and_sequences() {
+    assert_eq!(show("(+ (i. 2 2) 1)"), "[[1, 2], [3, 4]]");
+    assert_eq!(show("(count (i. 3 4))"), "3");
+    assert_eq!(show("(first (i. 3 4))"), "[0, 1, 2, 3]");
+}
+
+#[test]
+fn refuses_shapes_too_large_to_make() {
+    for source in ["(reshape [4294967296 4294967296] [1])", "(i. 4294967296 4294967296)", "(i. 0 4294967296 4294967296)", "(i. 100000 1000)"] {
+        assert!(show(source).ends_with("can't make an array of more than 16777216 items"), "{}", source);
+    }
+    assert_eq!(Array::new(vec![0, 1 << 40, 1 << 40], vec![]), None);
+    assert!(Array::new(vec![2, 1], vec![Primitive::Integer(1), Primitive::Integer(2)]).is_some());
+}
@@ -0,0 +1,45 @@
+use lisp_parser::{AstNode, BigInt, Primitive};
+
+mod common;
+
+fn value(source: &str) -> Primitive {
+    match common::eval(source).unwrap() {
+        AstNode::Value(value) => value,
+        other => panic!("expected a value, got {:?}", other),
+    }
+}
+
+fn big(digits: &str) -> Primitive {
+    Primitive::BigInteger(digits.parse::<BigInt>().unwrap())
+}
+
+#[test]
+fn overflow_promotes_to_a_bignum() {
+    assert_eq!(value("(+ 9223372036854775807 1)"), big("9223372036854775808"));
+    assert_eq!(value("(- -9223372036854775808 1)"), big("-9223372036854775809"));
+    assert_eq!(value("(* 4294967296 4294967296)"), big("18446744073709551616"));
+    assert_eq!(value("(^ 2 64)"), big("18446744073709551616"));
+    assert_eq!(value("(+ [9223372036854775807] 1)"), Primitive::Vector(vec![big("9223372036854775808")]));
+}
+
+#[test]
+fn negating_the_smallest_integer_promotes() {
+    assert_eq!(value("(* -1 -9223372036854775808)"), big("9223372036854775808"));
+    assert_eq!(value("(/ -9223372036854775808 -1)"), big("9223372036854775808"));
+    assert_eq!(value("(quot -9223372036854775808 -1)"), big("9223372036854775808"));
+}
+
+#[test]
+fn results_that_fit_become_integers_again() {
+    assert_eq!(value("(- (+ 9223372036854775807 1) 1)"), Primitive::Integer(i64::MAX));
+    assert_eq!(value("(- (^ 2 64) (^ 2 64))"), Primitive::Integer(0));
+    assert_eq!(value("(/ (* 4294967296 4294967296) 4294967296)"), Primitive::Integer(4294967296));
+    assert_eq!(value("(mod (^ 2 64) 7)"), Primitive::Integer(2));
+    assert_eq!(value("(^ 2 10)"), Primitive::Integer(1024));
+}
+
+#[test]
+fn bignums_mix_with_other_numbers() {
+    assert_eq!(value("(< 1 (^ 2 64))"), Primitive::Integer(1));
+    assert_eq!(value("(+ (^ 2 64) 0.5)"), Primitive::DoublePrecisionFloat(18446744073709551616.0));
+}
@@ -0,0 +1,76 @@
+use lisp_parser::{Broadcast, Interpreter, Pattern, Primitive};
+
+mod common;
+use common::{show, show_in};
+
+fn show_with(mode: Broadcast, source: &str) -> String {
+    let mut sauce = Interpreter::new();
+    sauce.set_broadcast(mode);
+    show_in(&mut sauce, source)
+}
+
+fn ints(items: &[i64]) -> Primitive {
+    Primitive::Vector(items.iter().copied().map(Primitive::Integer).collect())
+}
+
+#[test]
+fn combines_vectors_of_the_same_length_item_by_item() {
+    assert_eq!(show("(+ [1 2 3] [10 20 30])"), "[11, 22, 33]");
+    assert_eq!(show("(- [5 6] [1 2])"), "[4, 4]");
+    assert_eq!(show("(^ [2 3] [3 2])"), "[8, 9]");
+}
+
+#[test]
+fn combines_a_scalar_with_every_item_keeping_the_operand_order() {
+    assert_eq!(show("(- 10 [1 2])"), "[9, 8]");
+    assert_eq!(show("(- [1 2] 10)"), "[-9, -8]");
+    assert_eq!(show("(+ [[1 2] [3 4]] 1)"), "[[2, 3], [4, 5]]");
+}
+
+#[test]
+fn strict_mode_rejects_different_lengths() {
+    assert_eq!(show("(- [1 2] [5 6 7])"), "error: Cannot combine vectors of lengths 2 and 3");
+    assert_eq!(show("(* [1 2] [])"), "error: Cannot combine vectors of lengths 2 and 0");
+}
+
+#[test]
+fn fill_mode_pads_the_shorter_vector() {
+    assert_eq!(show_with(Broadcast::Fill, "(- [1 2] [5 6 7])"), "[-4, -4, -7]");
+    assert_eq!(show_with(Broadcast::Fill, "(- [5 6 7] [1 2])"), "[4, 4, 7]");
+    assert_eq!(show_with(Broadcast::Fill, r#"(+ ["a" "b"] ["c"])"#), r#"["ac", "b"]"#);
+}
+
+#[test]
+fn cycle_mode_repeats_the_shorter_vector() {
+    assert_eq!(show_with(Broadcast::Cycle, "(- [1 2] [5 6 7])"), "[-4, -4, -6]");
+    assert_eq!(show_with(Broadcast::Cycle, "(* [1 2 3 4] [1 10])"), "[1, 20, 3, 40]");
+    assert_eq!(show_with(Broadcast::Cycle, "(+ [1 2] [])"), "error: Cannot combine vectors of lengths 2 and 0");
+}
+
+#[test]
+fn modes_are_read_from_their_names() {
+    assert_eq!("fill".parse(), Ok(Broadcast::Fill));
+    assert_eq!("cycle".parse(), Ok(Broadcast::Cycle));
+    assert!("zip".parse::<Broadcast>().is_err());
+}
+
+#[test]
+fn the_operators_broadcast_without_panicking() {
+    assert_eq!(ints(&[1, 2]) + Primitive::Integer(1), Ok(ints(&[2, 3])));
+    assert_eq!(Primitive::Integer(10) - ints(&[1, 2]), Ok(ints(&[9, 8])));
+    assert!((ints(&[1, 2]) * ints(&[1, 2, 3])).is_err());
+    let regex = Primitive::Regex(Pattern::new("a").unwrap());
+    assert!((regex + Primitive::Integer(1)).is_err());
+}
+
+#[test]
+fn each_interpreter_has_its_own_mode() {
+    let mut fill = Interpreter::new();
+    fill.set_broadcast(Broadcast::Fill);
+    let mut strict = Interpreter::new();
+    assert_eq!(show_in(&mut fill, "(- [1 2] [5 6 7])"), "[-4, -4, -7]");
+    assert_eq!(show_in(&mut strict, "(- [1 2] [5 6 7])"), "error: Cannot combine vectors of lengths 2 and 3");
+    // Functions defined in an interpreter use its mode when it calls them.
+    fill.eval_str("(defn diff [xs ys] (- xs ys))").unwrap();
+    assert_eq!(show_in(&mut fill, "(diff [1 2] [5 6 7])"), "[-4, -4, -7]");
+}
@@ -0,0 +1,36 @@
+use lisp_parser::{EvalError, Interpreter};
+
+mod common;
+use common::show;
+
+#[test]
+fn operators_are_ordinary_functions() {
+    assert_eq!(show("+"), "#<native-fn +>");
+    assert_eq!(show("(map + [1 2] [10 20])"), "[11, 22]");
+    assert_eq!(show("(map < [1 5] [2 3])"), "[1, 0]");
+    assert_eq!(show("(map # [[1] [1 2]])"), "[1, 2]");
+    assert_eq!(show("(reduce * [1 2 3 4])"), "24");
+    assert_eq!(show("(let [plus +] (plus 1 2))"), "3");
+}
+
+#[test]
+fn operators_take_any_number_of_arguments() {
+    assert_eq!(show("(+)"), "0");
+    assert_eq!(show("(*)"), "1");
+    assert_eq!(show("(+ 1 2 3 4)"), "10");
+    assert_eq!(show(r#"(+ 1 "a" 2)"#), r#""1a2""#);
+}
+
+#[test]
+fn builtins_can_be_shadowed() {
+    assert_eq!(show("(let [+ -] (+ 5 3))"), "2");
+    assert_eq!(show("(defn + [a b] 0)\n(+ 1 2)"), "0");
+    // Shadowing only lasts as long as the interpreter that did it.
+    assert_eq!(show("(+ 1 2)"), "3");
+}
+
+#[test]
+fn unknown_names_are_unbound() {
+    assert_eq!(Interpreter::new().eval_str("(nosuchfn 1)"), Err(EvalError::UnboundSymbol("nosuchfn".to_string())));
+    assert_eq!(show("(count +)"), "error: Expected a collection, got #<native-fn +>");
+}
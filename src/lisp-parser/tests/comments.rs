@@ -0,0 +1,34 @@
+use lisp_parser::EvalError;
+
+mod common;
+use common::{eval, show};
+
+#[test]
+fn an_unterminated_block_comment_is_an_error() {
+    assert!(matches!(eval("#| unterminated"), Err(EvalError::Parse(_))));
+    assert!(matches!(eval("(+ 1 #| 2)"), Err(EvalError::Parse(_))));
+    assert_eq!(show("(# [1 2])"), "2");
+}
+
+#[test]
+fn line_comments_run_to_the_end_of_the_line() {
+    assert_eq!(show("(+ 1 2) ; three"), "3");
+    assert_eq!(show("(+ 1 ; one\n2)"), "3");
+    assert_eq!(show("; only a comment"), "[]");
+    assert_eq!(show(r#""; not a comment""#), r#""; not a comment""#);
+}
+
+#[test]
+fn discard_skips_the_next_form() {
+    assert_eq!(show("(+ 1 #_ 100 2)"), "3");
+    assert_eq!(show("(+ 1 #_(* 100 100) 2)"), "3");
+    assert_eq!(show("(+ 1 #_ #_ 10 20 2)"), "3");
+    assert_eq!(show("[1 #_2 3]"), "[1, 3]");
+    assert!(matches!(eval("#_"), Err(EvalError::Parse(_))));
+}
+
+#[test]
+fn block_comments_span_lines_and_nest() {
+    assert_eq!(show("(+ 1 #| block\ncomment |# 2)"), "3");
+    assert_eq!(show("#| outer #| inner |# still outer |# 5"), "5");
+}
@@ -0,0 +1,36 @@
+//! Helpers shared by the integration tests. Each test file uses only some
+//! of them.
+#![allow(dead_code)]
+
+use lisp_parser::{AstNode, EvalError, Interpreter, Primitive};
+
+/// Evaluates `source` in a fresh interpreter.
+pub fn eval(source: &str) -> Result<AstNode, EvalError> {
+    Interpreter::new().eval_str(source)
+}
+
+/// Items of a lazy sequence that `show` computes before printing `...`.
+pub const PRINT_LENGTH: usize = 100;
+
+/// The printed value of `source`, as the REPL prints it, or its error
+/// message after `error: `.
+pub fn show(source: &str) -> String {
+    show_in(&mut Interpreter::new(), source)
+}
+
+/// Like `show`, in an interpreter that has been set up beforehand.
+pub fn show_in(sauce: &mut Interpreter, source: &str) -> String {
+    let result = sauce.eval_str(source);
+    match result.and_then(|value| sauce.force(&value, PRINT_LENGTH).map(|()| value)) {
+        Ok(value) => value.to_string(),
+        Err(err) => format!("error: {}", err),
+    }
+}
+
+pub fn int(n: i64) -> AstNode {
+    AstNode::Value(Primitive::Integer(n))
+}
+
+pub fn string(s: &str) -> AstNode {
+    AstNode::Value(Primitive::Str(s.to_string()))
+}
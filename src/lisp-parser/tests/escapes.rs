@@ -0,0 +1,35 @@
+use lisp_parser::{EvalError, Primitive};
+
+mod common;
+use common::{eval, string};
+
+#[test]
+fn reads_escapes_in_double_quoted_strings() {
+    assert_eq!(eval(r#""a\nb""#), Ok(string("a\nb")));
+    assert_eq!(eval(r#""tab\there""#), Ok(string("tab\there")));
+    assert_eq!(eval(r#""say \"hi\"""#), Ok(string("say \"hi\"")));
+    assert_eq!(eval(r#""back\\slash""#), Ok(string("back\\slash")));
+    assert_eq!(eval(r#""\u{e9}\u{1F600}""#), Ok(string("é😀")));
+}
+
+#[test]
+fn single_quoted_strings_still_double_their_quotes() {
+    assert_eq!(eval("'it''s'"), Ok(string("it's")));
+    assert_eq!(eval(r#"'no \n escapes'"#), Ok(string("no \\n escapes")));
+}
+
+#[test]
+fn rejects_unknown_and_invalid_escapes() {
+    for source in [r#""\q""#, r#""\u{zz}""#, r#""\u{110000}""#, r#""unterminated\""#] {
+        assert!(matches!(eval(source), Err(EvalError::Parse(_))), "{} should not parse", source);
+    }
+}
+
+#[test]
+fn strings_print_as_literals_that_read_back() {
+    for s in ["plain", "a\nb\tc", "say \"hi\"", "C:\\temp", "bell\u{7}", "naïve 日本", ""] {
+        let printed = Primitive::Str(s.to_string()).to_string();
+        assert_eq!(eval(&printed), Ok(string(s)), "{} did not read back", printed);
+    }
+    assert_eq!(Primitive::Str("a\"b\n".to_string()).to_string(), r#""a\"b\n""#);
+}
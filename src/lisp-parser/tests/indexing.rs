@@ -0,0 +1,47 @@
+mod common;
+use common::show;
+
+#[test]
+fn indexes_from_either_end() {
+    assert_eq!(show("(nth [10 20 30] 1)"), "20");
+    assert_eq!(show("(nth [10 20 30] -1)"), "30");
+    assert_eq!(show(r#"(nth "héllo" 1)"#), r#""é""#);
+    assert_eq!(show(r#"(nth "héllo" -1)"#), r#""o""#);
+    assert_eq!(show("(nth (range) 5)"), "5");
+}
+
+#[test]
+fn out_of_range_indices_are_errors() {
+    assert_eq!(show("(nth [10 20 30] 3)"), "error: Index 3 is out of range for 3 items");
+    assert_eq!(show("(nth [10 20 30] -4)"), "error: Index -4 is out of range for 3 items");
+    assert_eq!(show("(nth [] 0)"), "error: Index 0 is out of range for 0 items");
+    assert_eq!(show("(nth [1] -9223372036854775808)"), "error: Index -9223372036854775808 is out of range for 1 items");
+    assert_eq!(show("(nth [1 2] 1.5)"), "error: Expected an integer, got 1.5");
+}
+
+#[test]
+fn get_gives_a_default_when_out_of_range() {
+    assert_eq!(show("(get [1 2] -1)"), "2");
+    assert_eq!(show("(get [1 2] 5)"), "[]");
+    assert_eq!(show("(get [1 2] 5 0)"), "0");
+}
+
+#[test]
+fn slices_vectors_and_strings() {
+    assert_eq!(show("(subvec [1 2 3 4] 1 3)"), "[2, 3]");
+    assert_eq!(show("(subvec [1 2 3 4] 2)"), "[3, 4]");
+    assert_eq!(show("(subvec [1 2 3 4] -2)"), "[3, 4]");
+    assert_eq!(show(r#"(subvec "héllo" 1 3)"#), r#""él""#);
+    assert_eq!(show("(subvec [1 2 3 4] 3 1)"), "error: Slice 3 to 1 is out of range for 4 items");
+    assert_eq!(show("(subvec [1 2 3 4] 1 9)"), "error: Slice 1 to 9 is out of range for 4 items");
+}
+
+#[test]
+fn from_selects_one_or_many_items() {
+    assert_eq!(show("({ 1 [10 20 30])"), "20");
+    assert_eq!(show("({ -1 [10 20 30])"), "30");
+    assert_eq!(show("({ [0 2] [10 20 30])"), "[10, 30]");
+    assert_eq!(show(r#"({ 1 "abc")"#), r#""b""#);
+    assert_eq!(show("({ 1 (i. 2 3))"), "[3, 4, 5]");
+    assert_eq!(show("({ 5 [10 20 30])"), "error: Index 5 is out of range for 3 items");
+}
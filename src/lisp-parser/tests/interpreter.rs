@@ -0,0 +1,61 @@
+use std::env;
+use std::fs;
+
+use lisp_parser::{EvalError, Interpreter};
+
+mod common;
+use common::int;
+
+#[test]
+fn globals_last_between_evaluations() {
+    let mut sauce = Interpreter::new();
+    sauce.eval_str("x := 20").unwrap();
+    sauce.eval_str("(defn inc [n] (+ n 1))").unwrap();
+    assert_eq!(sauce.eval_str("(inc x)"), Ok(int(21)));
+    assert_eq!(sauce.get("x"), Some(&int(20)));
+    assert!(sauce.globals().contains_key("inc"));
+}
+
+#[test]
+fn each_interpreter_has_its_own_globals() {
+    let mut first = Interpreter::new();
+    let mut second = Interpreter::new();
+    first.eval_str("x := 1").unwrap();
+    assert_eq!(second.eval_str("x"), Err(EvalError::UnboundSymbol("x".to_string())));
+    assert_eq!(second.get("x"), None);
+}
+
+#[test]
+fn the_host_can_set_globals_from_rust_values() {
+    let mut sauce = Interpreter::new();
+    sauce.set("name", "sauce");
+    sauce.set("sizes", vec![1, 2, 3]);
+    sauce.set("scale", 0.5);
+    assert_eq!(sauce.eval_str("(count name)"), Ok(int(5)));
+    assert_eq!(sauce.eval_str("(reduce + sizes)"), Ok(int(6)));
+    assert_eq!(sauce.eval_str("(* scale 4)").unwrap().to_string(), "2");
+}
+
+#[test]
+fn evaluates_files_and_reports_missing_ones() {
+    let path = env::temp_dir().join(format!("sauce-interpreter-test-{}.sauce", std::process::id()));
+    fs::write(&path, "(defn square [x] (* x x))\n(square 7)\n").unwrap();
+    let mut sauce = Interpreter::new();
+    let result = sauce.eval_file(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(result, Ok(int(49)));
+    assert_eq!(sauce.eval_str("(square 3)"), Ok(int(9)));
+
+    match sauce.eval_file(&path) {
+        Err(EvalError::Io(message)) => assert!(message.starts_with(&path.display().to_string()), "{}", message),
+        other => panic!("expected an IO error, got {:?}", other),
+    }
+}
+
+#[test]
+fn expands_macros_without_evaluating() {
+    let mut sauce = Interpreter::new();
+    sauce.eval_str("(defmacro twice [x] `(do ~x ~x))").unwrap();
+    let forms = sauce.expand_str("(twice (undefined))").unwrap();
+    assert_eq!(forms.iter().map(ToString::to_string).collect::<Vec<_>>(), ["[do, [undefined], [undefined]]"]);
+}
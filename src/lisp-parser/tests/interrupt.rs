@@ -0,0 +1,83 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use lisp_parser::{interrupt, AstNode, EvalError, Interpreter, Primitive};
+
+// There is one interrupt flag for the whole process.
+static FLAG: Mutex<()> = Mutex::new(());
+
+fn lock() -> std::sync::MutexGuard<'static, ()> {
+    FLAG.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Evaluates `source` while another thread keeps pressing Ctrl-C.
+fn interrupted(source: &str) -> Result<AstNode, EvalError> {
+    interrupted_in(&mut Interpreter::new(), source)
+}
+
+fn interrupted_in(sauce: &mut Interpreter, source: &str) -> Result<AstNode, EvalError> {
+    let done = AtomicBool::new(false);
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            while !done.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(10));
+                interrupt();
+            }
+        });
+        let result = sauce.eval_str(source);
+        done.store(true, Ordering::SeqCst);
+        result
+    })
+}
+
+#[test]
+fn an_interrupt_before_evaluation_is_forgotten() {
+    let _flag = lock();
+    interrupt();
+    assert_eq!(Interpreter::new().eval_str("(+ 1 2)"), Ok(AstNode::Value(Primitive::Integer(3))));
+}
+
+#[test]
+fn a_second_interrupt_reports_the_first_is_pending() {
+    let _flag = lock();
+    Interpreter::new().eval_str("0").unwrap();
+    assert!(!interrupt());
+    assert!(interrupt());
+}
+
+#[test]
+fn interrupts_stop_loops() {
+    let _flag = lock();
+    assert_eq!(interrupted("(loop [i 0] (recur (+ i 1)))"), Err(EvalError::Interrupted));
+}
+
+#[test]
+fn interrupts_stop_long_builtins() {
+    let _flag = lock();
+    assert_eq!(interrupted("(count (frequencies (range 100000000)))"), Err(EvalError::Interrupted));
+    assert_eq!(interrupted("(count (sort > (range 100000000)))"), Err(EvalError::Interrupted));
+}
+
+#[test]
+fn interrupts_stop_inserting_and_scanning() {
+    let _flag = lock();
+    // Multiplying big numbers is slow, and given several arguments `f/`
+    // folds them without walking a list first.
+    let mut sauce = Interpreter::new();
+    sauce.eval_str("n := (reduce * (repeat 5000 12345))").unwrap();
+    let operands = vec!["n"; 20].join(" ");
+    let insert = format!("((insert *) {})", operands);
+    assert_eq!(interrupted_in(&mut sauce, &insert), Err(EvalError::Interrupted));
+    let scan = format!("((scan *) {})", operands);
+    assert_eq!(interrupted_in(&mut sauce, &scan), Err(EvalError::Interrupted));
+}
+
+#[test]
+fn interrupts_stop_outer_products() {
+    let _flag = lock();
+    let mut sauce = Interpreter::new();
+    sauce.eval_str("ns := (repeat 10 (reduce * (repeat 5000 12345)))").unwrap();
+    assert_eq!(interrupted_in(&mut sauce, "(outer * ns ns)"), Err(EvalError::Interrupted));
+}
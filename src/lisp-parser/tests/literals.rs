@@ -0,0 +1,45 @@
+use lisp_parser::{AstNode, EvalError, Primitive};
+
+mod common;
+use common::{eval, int};
+
+fn float(x: f64) -> AstNode {
+    AstNode::Value(Primitive::DoublePrecisionFloat(x))
+}
+
+#[test]
+fn reads_negative_numbers_with_either_sign() {
+    assert_eq!(eval("-3"), Ok(int(-3)));
+    assert_eq!(eval("_3"), Ok(int(-3)));
+    assert_eq!(eval("(+ -3 _2)"), Ok(int(-5)));
+    assert_eq!(eval("(- 5 3)"), Ok(int(2)));
+}
+
+#[test]
+fn reads_scientific_notation() {
+    assert_eq!(eval("1e10"), Ok(float(1e10)));
+    assert_eq!(eval("2.5E-3"), Ok(float(0.0025)));
+    assert_eq!(eval("-1.5e2"), Ok(float(-150.0)));
+}
+
+#[test]
+fn reads_hexadecimal_and_binary() {
+    assert_eq!(eval("0xFF"), Ok(int(255)));
+    assert_eq!(eval("0Xff"), Ok(int(255)));
+    assert_eq!(eval("-0x10"), Ok(int(-16)));
+    assert_eq!(eval("0b1010"), Ok(int(10)));
+    assert_eq!(eval("(+ 0b1 0x1)"), Ok(int(2)));
+}
+
+#[test]
+fn reads_integers_too_big_for_64_bits() {
+    assert_eq!(eval("0xFFFFFFFFFFFFFFFFF").unwrap().to_string(), "295147905179352825855");
+    assert_eq!(eval("99999999999999999999").unwrap().to_string(), "99999999999999999999");
+}
+
+#[test]
+fn a_number_must_end_its_token() {
+    for source in ["0b102", "(+ 0b12 1)", "0xFG", "12abc", "1e", "1.5x", "3_"] {
+        assert!(matches!(eval(source), Err(EvalError::Parse(_))), "{} should not parse", source);
+    }
+}
@@ -0,0 +1,58 @@
+use lisp_parser::{EvalError, Interpreter};
+
+mod common;
+use common::show;
+
+const UNLESS: &str = "(defmacro unless [c body] `(if ~c 0 ~body))\n";
+
+#[test]
+fn macros_rewrite_their_unevaluated_arguments() {
+    assert_eq!(show(&format!("{}(unless 0 7)", UNLESS)), "7");
+    assert_eq!(show(&format!("{}(unless 1 (undefined))", UNLESS)), "0");
+}
+
+#[test]
+fn macroexpand_shows_the_expansion() {
+    assert_eq!(show(&format!("{}(macroexpand '(unless 0 7))", UNLESS)), "[if, 0, 0, 7]");
+}
+
+#[test]
+fn macroexpand_1_expands_only_once() {
+    let source = "(defmacro twice [x] `(unless 0 ~x))\n";
+    assert_eq!(show(&format!("{}{}(macroexpand-1 '(twice 7))", UNLESS, source)), "[unless, 0, 7]");
+    assert_eq!(show(&format!("{}{}(macroexpand '(twice 7))", UNLESS, source)), "[if, 0, 0, 7]");
+}
+
+#[test]
+fn macros_take_rest_parameters() {
+    let source = "(defmacro my-do [& forms] `(do ~@forms))\n(my-do 1 2 3)";
+    assert_eq!(show(source), "3");
+}
+
+#[test]
+fn gensym_makes_fresh_symbols() {
+    let mut interpreter = Interpreter::new();
+    let a = interpreter.eval_str("(gensym)").unwrap();
+    let b = interpreter.eval_str("(gensym)").unwrap();
+    assert_ne!(a, b);
+    assert!(show("(gensym \"tmp\")").starts_with("tmp__"));
+}
+
+#[test]
+fn gensym_keeps_macro_bindings_hygienic() {
+    let swap = "(defmacro swap [a b] (let [t (gensym)] `(let [~t ~a] (list ~b ~t))))\n";
+    assert_eq!(show(&format!("{}(swap 1 2)", swap)), "[2, 1]");
+    assert_eq!(show(&format!("{}t := 9\n(swap t 2)", swap)), "[2, 9]");
+}
+
+#[test]
+fn defmacro_checks_its_parameters() {
+    let err = Interpreter::new().eval_str("(defmacro bad [1] 1)").unwrap_err();
+    assert!(matches!(err, EvalError::InvalidForm(_)));
+}
+
+#[test]
+fn macros_can_use_what_the_forms_before_them_defined() {
+    assert_eq!(show("(defn wrap [x] `(+ ~x 1))\n(defmacro inc1 [x] (wrap x))\n(inc1 5)"), "6");
+    assert_eq!(show("n := 3\n(defmacro m [] n)\n(m)"), "3");
+}
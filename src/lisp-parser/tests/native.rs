@@ -0,0 +1,55 @@
+use std::convert::TryFrom;
+
+use lisp_parser::{AstNode, EvalError, Interpreter, NativeFn, Primitive};
+
+fn show(sauce: &mut Interpreter, source: &str) -> String {
+    match sauce.eval_str(source) {
+        Ok(value) => value.to_string(),
+        Err(err) => format!("error: {}", err),
+    }
+}
+
+fn with_natives() -> Interpreter {
+    let mut sauce = Interpreter::new();
+    sauce.register_fn("sqrt", |args| Ok(f64::try_from(&args[0])?.sqrt().into()));
+    sauce.register_fn("shout", |args| Ok(String::try_from(&args[0])?.to_uppercase().into()));
+    sauce.register_fn("total", |args| Ok(Vec::<i64>::try_from(&args[0])?.iter().sum::<i64>().into()));
+    sauce.register_fn("halves", |args| {
+        let n = i32::try_from(&args[0])?;
+        Ok(AstNode::Value(vec![n / 2, n - n / 2].into()))
+    });
+    sauce
+}
+
+#[test]
+fn sauce_can_call_registered_functions() {
+    let mut sauce = with_natives();
+    assert_eq!(show(&mut sauce, "(sqrt 16)"), "4");
+    assert_eq!(show(&mut sauce, r#"(shout "hi")"#), r#""HI""#);
+    assert_eq!(show(&mut sauce, "(total [1 2 3])"), "6");
+    assert_eq!(show(&mut sauce, "(total (range 4))"), "6");
+    assert_eq!(show(&mut sauce, "(halves 5)"), "[2, 3]");
+}
+
+#[test]
+fn registered_functions_are_values() {
+    let mut sauce = with_natives();
+    assert_eq!(show(&mut sauce, "(map sqrt [1 4 9])"), "[1, 2, 3]");
+    assert_eq!(show(&mut sauce, "(defn twice [f x] (f (f x)))\n(twice sqrt 16)"), "2");
+}
+
+#[test]
+fn arguments_of_the_wrong_type_are_errors() {
+    let mut sauce = with_natives();
+    assert_eq!(show(&mut sauce, "(shout 1)"), "error: Expected a string, got 1");
+    assert_eq!(show(&mut sauce, r#"(total [1 "a"])"#), r#"error: Expected an integer, got "a""#);
+    assert_eq!(show(&mut sauce, "(halves 9999999999)"), "error: Expected a 32-bit integer, got 9999999999");
+}
+
+#[test]
+fn native_functions_can_be_called_from_rust() {
+    let double = NativeFn::new("double", |args| Ok((i64::try_from(&args[0])? * 2).into()));
+    assert_eq!(double.call(&[AstNode::from(21)]), Ok(AstNode::Value(Primitive::Integer(42))));
+    let error = double.call(&[AstNode::from("x")]);
+    assert!(matches!(error, Err(EvalError::TypeMismatch { .. })));
+}
@@ -0,0 +1,43 @@
+use lisp_parser::{EvalError, Interpreter};
+
+mod common;
+use common::show;
+
+fn error(source: &str) -> EvalError {
+    Interpreter::new().eval_str(source).unwrap_err()
+}
+
+#[test]
+fn quote_returns_forms_unevaluated() {
+    assert_eq!(show("'(1 2 3)"), "[1, 2, 3]");
+    assert_eq!(show("'a"), "a");
+    assert_eq!(show("'(+ 1 2)"), "[+, 1, 2]");
+}
+
+#[test]
+fn unquote_evaluates_inside_a_quasiquote() {
+    assert_eq!(show("x := 5\n`(a ~x)"), "[a, 5]");
+    assert_eq!(show("`(a ~(+ 1 2))"), "[a, 3]");
+}
+
+#[test]
+fn unquote_splicing_inserts_the_items_of_a_list() {
+    assert_eq!(show("xs := (list 1 2)\n`(0 ~@xs 3)"), "[0, 1, 2, 3]");
+    assert_eq!(show("xs := (list 1 2)\n`[a ~@xs]"), "[a 1 2]");
+}
+
+#[test]
+fn nested_lists_are_walked() {
+    assert_eq!(show("x := 5\n`(a (b ~x))"), "[a, [b, 5]]");
+}
+
+#[test]
+fn unquote_outside_a_quasiquote_is_an_error() {
+    assert_eq!(error("x := 5\n~x"), EvalError::UnquoteOutsideQuasiquote);
+}
+
+#[test]
+fn splicing_needs_a_list_to_splice_into_and_from() {
+    assert_eq!(error("xs := (list 1 2)\n`~@xs"), EvalError::SpliceOutsideList);
+    assert!(matches!(error("`(a ~@5)"), EvalError::NotSpliceable(_)));
+}
@@ -0,0 +1,75 @@
+use lisp_parser::{EvalError, Interpreter, Primitive};
+
+mod common;
+use common::show;
+
+fn error(source: &str) -> String {
+    match Interpreter::new().eval_str(source) {
+        Err(EvalError::Operation(err)) => err.to_string(),
+        other => panic!("expected an operation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn uneven_integer_division_is_exact() {
+    assert_eq!(show("(/ 7 2)"), "7/2");
+    assert_eq!(show("(/ 6 3)"), "2");
+    assert_eq!(show("(/ [6 3] 4)"), "[3/2, 3/4]");
+    assert_eq!(show("(+ (/ 1 2) (/ 1 3))"), "5/6");
+    assert_eq!(show("(* (/ 2 3) 3)"), "2");
+}
+
+#[test]
+fn ratios_compare_by_value() {
+    assert_eq!(show("(< (/ 1 3) (/ 1 2))"), "1");
+    assert_eq!(show("(== (/ 2 4) (/ 1 2))"), "1");
+}
+
+#[test]
+fn floats_make_division_inexact() {
+    assert_eq!(show("(/ 7 2.0)"), "3.5");
+    assert_eq!(show("(+ (/ 1 2) 0.25)"), "0.75");
+}
+
+#[test]
+fn takes_ratios_apart() {
+    assert_eq!(show("(numerator (/ 6 4))"), "3");
+    assert_eq!(show("(denominator (/ 6 4))"), "2");
+    assert_eq!(show("(denominator 5)"), "1");
+}
+
+#[test]
+fn rounds_integer_division() {
+    assert_eq!(show("(quot 7 2)"), "3");
+    assert_eq!(show("(quot -7 2)"), "-3");
+    assert_eq!(show("(rem -7 2)"), "-1");
+    assert_eq!(show("(mod -7 2)"), "1");
+}
+
+#[test]
+fn dividing_by_zero_is_an_error() {
+    assert_eq!(error("(/ 1 0)"), "Divide by zero");
+    assert_eq!(error("(quot 1 0)"), "Divide by zero");
+    assert_eq!(error("(mod 7 0)"), "Divide by zero");
+}
+
+#[test]
+fn dividing_a_float_by_zero_follows_ieee() {
+    assert_eq!(show("(/ 1.0 0)"), "inf");
+    assert_eq!(show("(/ -1.0 0)"), "-inf");
+    assert_eq!(show("(/ [1.0 -2.0] 0)"), "[inf, -inf]");
+    assert_eq!(error("(/ (/ 1 2) 0)"), "Divide by zero");
+}
+
+#[test]
+fn dividing_by_zero_inside_a_vector_is_an_error() {
+    assert_eq!(error("(/ [1 2] [0 1])"), "Divide by zero");
+    assert_eq!(error("(/ 2 [1 0])"), "Divide by zero");
+    assert_eq!(error("(/ [[1] [2]] [[1] [0]])"), "Divide by zero");
+}
+
+#[test]
+fn the_division_operator_reports_division_by_zero() {
+    assert!((Primitive::Integer(1) / Primitive::Integer(0)).is_err());
+    assert!((Primitive::Vector(vec![Primitive::Integer(1)]) / Primitive::Integer(0)).is_err());
+}
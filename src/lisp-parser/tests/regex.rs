@@ -0,0 +1,39 @@
+use lisp_parser::{EvalError, Interpreter};
+
+mod common;
+use common::show;
+
+#[test]
+fn regex_literals_read_and_print_back() {
+    assert_eq!(show(r#"#"a+""#), r#"#"a+""#);
+    assert_eq!(show(r#"(re-find #"a\"b" "xa\"b")"#), r#""a\"b""#);
+    assert!(matches!(Interpreter::new().eval_str(r#"#"(""#), Err(EvalError::Parse(_))));
+}
+
+#[test]
+fn finds_the_first_match_with_its_groups() {
+    assert_eq!(show(r#"(re-find #"\d+" "ab 12 cd 34")"#), r#""12""#);
+    assert_eq!(show(r#"(re-find #"(\w+)@(\w+)" "mail bob@host now")"#), r#"["bob@host", "bob", "host"]"#);
+    assert_eq!(show(r#"(re-find #"(a)(x)?" "a")"#), r#"["a", "a", []]"#);
+    assert_eq!(show(r#"(re-find #"x" "abc")"#), "[]");
+}
+
+#[test]
+fn matches_only_the_whole_string() {
+    assert_eq!(show(r#"(re-matches #"\d+" "123")"#), r#""123""#);
+    assert_eq!(show(r#"(re-matches #"\d+" "123a")"#), "[]");
+    assert_eq!(show(r#"(re-matches #"(\d)(\d)" "12")"#), r#"["12", "1", "2"]"#);
+}
+
+#[test]
+fn finds_every_match() {
+    assert_eq!(show(r#"(re-seq #"\d" "a1b2c3")"#), r#"["1", "2", "3"]"#);
+    assert_eq!(show(r#"(re-seq #"(\w)=(\d)" "a=1 b=2")"#), r#"[["a=1", "a", "1"], ["b=2", "b", "2"]]"#);
+}
+
+#[test]
+fn replaces_every_match_using_groups() {
+    assert_eq!(show(r#"(re-replace #"\d" "a1b2" "-")"#), r#""a-b-""#);
+    assert_eq!(show(r#"(re-replace #"(\w+)@(\w+)" "bob@host" "$2 at $1")"#), r#""host at bob""#);
+    assert_eq!(show(r#"(re-find "a" "abc")"#), r#"error: Expected a regex, got "a""#);
+}
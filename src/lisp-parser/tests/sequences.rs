@@ -0,0 +1,100 @@
+use lisp_parser::{AstNode, EvalError, Interpreter};
+
+mod common;
+use common::show;
+
+#[test]
+fn counts_items_in_order_of_first_appearance() {
+    assert_eq!(show(r#"(frequencies ["b" "a" "b" "c" "b"])"#), r#"[["b", 3], ["a", 1], ["c", 1]]"#);
+    assert_eq!(show("(frequencies [[1 2] 1 [1 2]])"), "[[[1, 2], 2], [1, 1]]");
+}
+
+#[test]
+fn counts_only_equal_values_together() {
+    assert_eq!(show("(frequencies [1 1.0 1])"), "[[1, 2], [1, 1]]");
+    assert_eq!(show("(count (frequencies [-0.0 0.0]))"), "1");
+    assert_eq!(show("(frequencies [(/ 0.0 0.0) (/ 0.0 0.0)])"), "[[NaN, 1], [NaN, 1]]");
+}
+
+#[test]
+fn counts_large_collections_quickly() {
+    assert_eq!(show("(count (frequencies (range 100000)))"), "100000");
+    assert_eq!(show("(count (group-by (fn [x] x) (range 100000)))"), "100000");
+}
+
+#[test]
+fn groups_by_key_in_order_of_first_appearance() {
+    assert_eq!(show("(group-by (fn [x] (mod x 3)) (range 7))"), "[[0, [0, 3, 6]], [1, [1, 4]], [2, [2, 5]]]");
+    assert_eq!(show(r#"(group-by count ["a" "bb" "c"])"#), r#"[[1, ["a", "c"]], [2, ["bb"]]]"#);
+}
+
+#[test]
+fn sorts_stably() {
+    assert_eq!(show("(sort [3 1 2])"), "[1, 2, 3]");
+    assert_eq!(show("(sort > [3 1 2])"), "[3, 2, 1]");
+    assert_eq!(show(r#"(sort-by count ["ccc" "a" "bb" "d"])"#), r#"["a", "d", "bb", "ccc"]"#);
+}
+
+#[test]
+fn arity_errors_give_the_allowed_range() {
+    assert_eq!(show("(range 1 2 3 4)"), "error: Wrong number of args (4) passed to range, expected 0 to 3");
+    assert_eq!(show("(first)"), "error: Wrong number of args (0) passed to first, expected 1");
+    assert_eq!(show("(map)"), "error: Wrong number of args (0) passed to map, expected at least 2");
+    assert_eq!(show("(defn f [a & more] a)\n(f)"), "error: Wrong number of args (0) passed to f, expected at least 1");
+    let arity = Interpreter::new().eval_str("(if)");
+    assert_eq!(arity, Err(EvalError::Arity { name: "if".to_string(), min: 2, max: 3, given: 0 }));
+}
+
+#[test]
+fn finite_ranges_and_repeats_are_lazy() {
+    assert_eq!(show("(first (range 100000000000))"), "0");
+    assert_eq!(show("(nth (range 5 100000000000 5) 3)"), "20");
+    assert_eq!(show("(take 3 (repeat 99999999999999999 1))"), "[1, 1, 1]");
+}
+
+#[test]
+fn counts_ranges_up_down_and_by_fractions() {
+    assert_eq!(show("(range 5)"), "[0, 1, 2, 3, 4]");
+    assert_eq!(show("(range 10 0 -3)"), "[10, 7, 4, 1]");
+    assert_eq!(show("(range 0 1 0.25)"), "[0, 0.25, 0.5, 0.75]");
+    assert_eq!(show("(range 9223372036854775806 9223372036854775807 5)"), "[9223372036854775806]");
+    assert_eq!(show("(range 0 5 0)"), "error: range needs a non-zero step");
+    assert_eq!(show("(repeat 3 \"a\")"), "[\"a\", \"a\", \"a\"]");
+    assert_eq!(show("(repeat -2 1)"), "[]");
+}
+
+#[test]
+fn infinite_sequences_compute_only_what_is_used() {
+    assert_eq!(show("(take 3 (map (fn [x] (* x x)) (range)))"), "[0, 1, 4]");
+    assert_eq!(show("(take 4 (iterate (fn [x] (* 2 x)) 1))"), "[1, 2, 4, 8]");
+    assert_eq!(show("(take 5 (cycle [1 2]))"), "[1, 2, 1, 2, 1]");
+    assert_eq!(show("(first (filter (fn [x] (> x 100)) (range)))"), "101");
+    assert_eq!(show("(take 2 (drop 5 (repeat 7)))"), "[7, 7]");
+    assert_eq!(show("(defn nat [n] (lazy-seq (cons n (nat (+ n 1)))))\n(take 3 (nat 4))"), "[4, 5, 6]");
+}
+
+#[test]
+fn lazy_sequences_are_realized_for_other_functions() {
+    assert_eq!(show("(+ (range 3) 1)"), "[1, 2, 3]");
+    assert_eq!(show("(count (range 5))"), "5");
+    assert_eq!(show("(reduce + (take 4 (range)))"), "6");
+    assert_eq!(show("(zip (range) [\"a\" \"b\"])"), "[[0, \"a\"], [1, \"b\"]]");
+}
+
+#[test]
+fn lazy_results_are_returned_unrealized() {
+    let mut sauce = Interpreter::new();
+    let value = sauce.eval_str("(iterate (fn [x] (* 2 x)) 1)").unwrap();
+    assert!(matches!(value, AstNode::LazySeq(_)));
+    assert_eq!(value.to_string(), "[...]");
+    sauce.force(&value, 3).unwrap();
+    assert_eq!(value.to_string(), "[1, 2, 4, ...]");
+    assert!(matches!(sauce.eval_str("y := (range)\n(do y)"), Ok(AstNode::LazySeq(_))));
+}
+
+#[test]
+fn printing_stops_after_a_hundred_items() {
+    let printed = show("(range)");
+    assert!(printed.starts_with("[0, 1, 2,") && printed.ends_with("98, 99, ...]"), "{}", printed);
+    assert_eq!(show("[(range 2) (repeat 3)]"), format!("[[0, 1], [{}, ...]]", vec!["3"; 100].join(", ")));
+}
@@ -0,0 +1,107 @@
+use std::sync::Mutex;
+
+use lisp_parser::{set_max_depth, AstNode, EvalError, Interpreter, Primitive, DEFAULT_MAX_DEPTH};
+
+mod common;
+
+// The depth limit is shared by every interpreter, so the test that changes
+// it runs alone.
+static LIMIT: Mutex<()> = Mutex::new(());
+
+const COUNT: &str = "(defn count-down [n] (if (== n 0) 0 (+ 1 (count-down (- n 1)))))\n";
+
+fn eval(source: &str) -> Result<AstNode, EvalError> {
+    let _limit = LIMIT.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    common::eval(source)
+}
+
+fn nested(open: &str, close: &str, depth: usize) -> String {
+    format!("{}1{}", open.repeat(depth), close.repeat(depth))
+}
+
+#[test]
+fn deep_recursion_is_an_error_on_an_ordinary_thread() {
+    let result = std::thread::Builder::new()
+        .stack_size(2 << 20)
+        .spawn(|| eval(&format!("{}(count-down 1000000)", COUNT)))
+        .unwrap()
+        .join()
+        .unwrap();
+    assert!(matches!(result, Err(EvalError::StackOverflow { .. })));
+}
+
+#[test]
+fn the_depth_limit_can_be_lowered() {
+    let _limit = LIMIT.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    set_max_depth(50);
+    let deep = Interpreter::new().eval_str(&format!("{}(count-down 100)", COUNT));
+    let shallow = Interpreter::new().eval_str(&format!("{}(count-down 10)", COUNT));
+    set_max_depth(DEFAULT_MAX_DEPTH);
+    match deep {
+        Err(EvalError::StackOverflow { depth, chain }) => {
+            assert_eq!(depth, 50);
+            assert!(chain.iter().all(|name| name == "count-down"));
+        }
+        other => panic!("expected a stack overflow, got {:?}", other),
+    }
+    assert_eq!(shallow, Ok(AstNode::Value(Primitive::Integer(10))));
+}
+
+#[test]
+fn source_nested_too_deeply_is_a_parse_error() {
+    for source in [nested("(", ")", 5000), nested("'[", "]", 20000), format!("{}1", "'".repeat(2000))] {
+        match eval(&source) {
+            Err(EvalError::Parse(err)) => assert!(err.to_string().contains("nested more than 1000 levels")),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn brackets_in_strings_and_comments_do_not_count_as_nesting() {
+    let brackets = "(".repeat(2000);
+    let source = format!("; {}\n#| {} |# \"{}\" '{}' #\"[{}]\"", brackets, brackets, brackets, brackets, brackets);
+    assert!(lisp_parser::parse(&source).is_ok());
+}
+
+#[test]
+fn deeply_quoted_data_is_read_or_rejected_without_aborting() {
+    let source = format!("`{}", nested("[", "]", 999));
+    let result = std::thread::Builder::new()
+        .stack_size(2 << 20)
+        .spawn(move || eval(&source).map(|_| ()))
+        .unwrap()
+        .join()
+        .unwrap();
+    assert!(matches!(result, Ok(()) | Err(EvalError::StackOverflow { .. })));
+}
+
+fn on_small_stack(source: &'static str) -> Result<String, EvalError> {
+    std::thread::Builder::new()
+        .stack_size(2 << 20)
+        .spawn(move || eval(source).map(|value| value.to_string()))
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+#[test]
+fn long_chains_of_closures_and_lazy_sequences_are_dropped_without_aborting() {
+    let closures = on_small_stack("(loop [i 0 f (fn [] 0)] (if (< i 50000) (recur (+ i 1) (fn [] f)) i))");
+    assert_eq!(closures, Ok("50000".to_string()));
+    let wrapped = on_small_stack("(loop [i 0 f (fn [] 0)] (if (< i 50000) (recur (+ i 1) [(fn [] f)]) i))");
+    assert_eq!(wrapped, Ok("50000".to_string()));
+    let lazy = on_small_stack("(loop [i 0 s 0] (if (< i 50000) (recur (+ i 1) (lazy-seq [s])) i))");
+    assert_eq!(lazy, Ok("50000".to_string()));
+}
+
+#[test]
+fn values_nested_too_deeply_are_an_error() {
+    for source in ["(reduce (fn [acc x] (list acc)) [] (range 20000))", "(reduce (fn [acc x] [acc]) [] (range 20000))"] {
+        match on_small_stack(source) {
+            Err(err) => assert_eq!(err.to_string(), "Values can't be nested more than 1000 levels deep"),
+            other => panic!("expected {} to fail, got {:?}", source, other),
+        }
+    }
+    assert_eq!(on_small_stack("(count (reduce (fn [acc x] (list acc)) [] (range 999)))"), Ok("1".to_string()));
+}
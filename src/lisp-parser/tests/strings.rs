@@ -0,0 +1,68 @@
+use lisp_parser::{AstNode, EvalError, Interpreter, Primitive};
+
+mod common;
+use common::{eval, int, string};
+
+#[test]
+fn concatenates_without_a_leading_zero() {
+    assert_eq!(eval(r#"(+ "naïve" " café")"#), Ok(string("naïve café")));
+}
+
+#[test]
+fn interleaves_by_character() {
+    assert_eq!(eval(r#"(* "héllo" "wö")"#), Ok(string("hwéöllo")));
+    assert_eq!(eval(r#"(* "ü" "日本語")"#), Ok(string("ü日本語")));
+}
+
+#[test]
+fn repeats_by_character() {
+    assert_eq!(eval(r#"(* 2 "ñ")"#), Ok(string("ññ")));
+    assert_eq!(eval(r#"(* "ñ" 2)"#), Ok(string("ññ")));
+    assert_eq!(eval(r#"(* 1.5 "日本")"#), Ok(string("日本日")));
+    assert_eq!(eval(r#"(* "🎉ab" 0.5)"#), Ok(string("🎉")));
+    assert_eq!(eval(r#"(* -1 "é")"#), Ok(string("")));
+}
+
+#[test]
+fn refuses_to_repeat_a_string_without_bound() {
+    for source in [r#"(* 9999999999999999 "ab")"#, r#"(* "ab" 1e300)"#, r#"(* 99999999999999999999999 "a")"#] {
+        match Interpreter::new().eval_str(source) {
+            Err(EvalError::Operation(err)) => assert!(err.to_string().starts_with("Cannot repeat a string")),
+            other => panic!("expected {} to fail, got {:?}", source, other),
+        }
+    }
+}
+
+#[test]
+fn divides_by_character() {
+    assert_eq!(eval(r#"(/ "日本語です" 2)"#), Ok(string("日本語")));
+    assert_eq!(eval(r#"(/ "ééé" 3)"#), Ok(string("é")));
+    assert_eq!(eval(r#"(/ "αβγδ" 2.0)"#), Ok(string("αβ")));
+    assert_eq!(eval(r#"(/ 0 "αβ")"#), Ok(string("")));
+}
+
+#[test]
+fn splits_and_removes_multibyte_substrings() {
+    let parts = vec![Primitive::Str("a".to_string()), Primitive::Str("b".to_string())];
+    assert_eq!(eval(r#"(/ "a→b" "→")"#), Ok(AstNode::Value(Primitive::Vector(parts))));
+    assert_eq!(eval(r#"(- "crème brûlée" "û")"#), Ok(string("crème brlée")));
+}
+
+#[test]
+fn counts_characters() {
+    assert_eq!(eval(r#"(# "日本語")"#), Ok(int(3)));
+}
+
+#[test]
+fn indexes_library_functions_by_character() {
+    assert_eq!(eval(r#"(subs "héllo wörld" 6 9)"#), Ok(string("wör")));
+    assert_eq!(eval(r#"(index-of "naïve café" "café")"#), Ok(int(6)));
+    assert_eq!(eval(r#"(index-of "日本日本" "本" 2)"#), Ok(int(3)));
+}
+
+#[test]
+fn joins_and_formats() {
+    assert_eq!(eval(r#"(join "→" (split "a,b,c" ","))"#), Ok(string("a→b→c")));
+    assert_eq!(eval(r#"(format "%s: %d, %.1f%%" "ü" 3 99.44)"#), Ok(string("ü: 3, 99.4%")));
+    assert_eq!(eval(r#"(str "x" 1 (/ 1 2))"#), Ok(string("x11/2")));
+}
@@ -0,0 +1,45 @@
+use lisp_parser::{AstNode, EvalError};
+
+mod common;
+use common::{eval, int};
+
+#[test]
+fn recurs_without_growing_the_stack() {
+    let sum = "(loop [i 0 acc 0] (if (< i 20000) (recur (+ i 1) (+ acc i)) acc))";
+    assert_eq!(eval(sum), Ok(int(199990000)));
+}
+
+#[test]
+fn calls_in_tail_position_run_in_constant_stack() {
+    let countdown = "(defn down [n] (if (== n 0) 0 (down (- n 1))))\n(down 20000)";
+    assert_eq!(eval(countdown), Ok(int(0)));
+}
+
+#[test]
+fn recurs_from_a_function_body() {
+    let fact = "(defn fact [n acc] (if (< n 2) acc (recur (- n 1) (* acc n))))\n(fact 20 1)";
+    assert_eq!(eval(fact), Ok(int(2432902008176640000)));
+}
+
+#[test]
+fn closures_keep_the_values_of_their_iteration() {
+    let source = "(loop [i 0 fs (list)] (if (< i 3) (recur (+ i 1) (cons (fn [] i) fs)) (map (fn [f] (f)) fs)))";
+    assert_eq!(eval(source), Ok(AstNode::Terms(vec![int(2), int(1), int(0)])));
+}
+
+#[test]
+fn bindings_inside_the_body_do_not_outlive_an_iteration() {
+    let source = "(let [x 5] (loop [i 0] (if (< i 2) (let [x 1] (recur (+ i 1))) x)))";
+    assert_eq!(eval(source), Ok(int(5)));
+}
+
+#[test]
+fn recur_outside_a_loop_is_an_error() {
+    assert!(matches!(eval("(recur 1)"), Err(EvalError::InvalidForm(_))));
+}
+
+#[test]
+fn recur_checks_its_arity() {
+    let source = "(loop [i 0] (if (< i 3) (recur (+ i 1) 2) i))";
+    assert!(matches!(eval(source), Err(EvalError::Arity { .. })));
+}
@@ -1,31 +1,81 @@
-use lisp_parser::AstNode;
+use lisp_parser::Interpreter;
 use rustyline::error::ReadlineError;
-use rustyline::{Editor, Result};
-use colored::Colorize;
+use rustyline::{Config, Editor, Result};
+use colored::{Color, Colorize};
 use tracing::{warn, info, error};
-use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
 
+use config::Options;
+mod config;
+
+
+/// The REPL runs on its own thread so that deep (non-tail) recursion reaches
+/// the evaluator's depth limit before it runs out of native stack.
+const REPL_STACK_SIZE: usize = 512 * 1024 * 1024;
+
+/// Items of a lazy sequence the REPL computes to print it, before `...`.
+const PRINT_LENGTH: usize = 100;
 
 fn main() {
-    println!("{} Lisp Version 0.0.1", format!("Sauce").red());
+    let options = match config::parse_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+
+    colored::control::set_override(options.use_color());
+
+    println!("{} Lisp Version 0.0.1", "Sauce".red());
     println!("Press Ctrl+c or type \"q\" to Exit\n");
     
-    ui().map_err(|err| println!("{:?}", err)).ok();
+    // rustyline reports Ctrl-C itself while reading a line. Any other time it
+    // cancels the expression being evaluated and returns to the prompt, and
+    // a second one before the first is noticed exits.
+    ctrlc::set_handler(|| {
+        if lisp_parser::interrupt() {
+            std::process::exit(130);
+        }
+    })
+    .expect("unable to install the Ctrl-C handler");
+
+    let repl = std::thread::Builder::new()
+        .stack_size(REPL_STACK_SIZE)
+        .spawn(|| ui(options).map_err(|err| println!("{:?}", err)).ok())
+        .expect("unable to start the REPL thread");
+    repl.join().ok();
 }
 
-fn ui() -> Result<()>  {
-    let mut rl = Editor::<()>::new()?;
-    if rl.load_history("./.sauce-history.txt").is_err() {
-        println!("No history found.");
+fn ui(options: Options) -> Result<()>  {
+    let config = Config::builder()
+        .max_history_size(options.history_size)
+        .edit_mode(options.edit_mode)
+        .build();
+    let mut rl = Editor::<()>::with_config(config)?;
+    let history_file = options.history_file;
+    if let Some(path) = &history_file {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).ok();
+        }
+        if rl.load_history(path).is_err() {
+            println!("No history found.");
+        }
+    }
+    let mut sauce = Interpreter::new();
+    sauce.set_broadcast(options.broadcast);
+    if let Some(path) = &options.init_file {
+        load_init_file(&mut sauce, path, options.error_color);
     }
-    let mut saucy_var: HashMap<String, AstNode> = HashMap::new();
+    let prompt = format!("{}> ", options.prompt.color(options.prompt_color));
 
     loop {
-        let input = rl.readline(&format!("{}> ", "s".red()));
+        let input = rl.readline(&prompt);
         match input {
             Ok(line) => {
                 let skinny = line.trim();
-                if skinny == "" {
+                if skinny.is_empty() {
                     continue;
                 }
                 if skinny.to_lowercase() == "q" {
@@ -33,7 +83,17 @@ fn ui() -> Result<()>  {
                     break;
                 }
                 rl.add_history_entry::<&str>(line.as_str().as_ref());
-                let result = evaluate_input(skinny, &mut saucy_var);
+                // Saved as we go so a crash or kill doesn't lose the session.
+                if let Some(path) = &history_file {
+                    if let Err(err) = rl.append_history(path) {
+                        warn!("Unable to save history: {:?}", err);
+                    }
+                }
+                if let Some(form) = skinny.strip_prefix(":expand") {
+                    expand_input(&mut sauce, form.trim(), options.error_color);
+                    continue;
+                }
+                let result = evaluate_input(&mut sauce, skinny, options.error_color);
                 match result {
                     Ok(_) => {
                         println!("Input Processed");
@@ -59,7 +119,6 @@ fn ui() -> Result<()>  {
             }
         }
     }
-    rl.save_history("./.sauce-history.txt")?;
     Ok(())
 }
 
@@ -67,14 +126,34 @@ fn toodles() {
     println!("Have a {} Day", "Saucy".red())
 }
 
-fn evaluate_input(input: &str, saucy_vars: &mut HashMap<String, AstNode>) -> Result<()>{
-    //parse this should be replaced with: https://github.com/pest-parser/pest
-    let astnode = lisp_parser::parse(input).expect("unsuccessful parse");
-    println!("{:?}", &astnode);
-
+fn evaluate_input(sauce: &mut Interpreter, input: &str, error_color: Color) -> Result<()> {
     println!("Evaluating...");
-    let value =lisp_parser::evaluate(astnode, saucy_vars).expect("unsuccessful evaluation");
-    println!("Evaluation: {}", value);
+    match sauce.eval_str(input).and_then(|value| sauce.force(&value, PRINT_LENGTH).map(|()| value)) {
+        Ok(value) => println!("Evaluation: {}", value),
+        Err(err) => println!("{} {}", "Error:".color(error_color), err),
+    }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+fn expand_input(sauce: &mut Interpreter, input: &str, error_color: Color) {
+    match sauce.expand_str(input) {
+        Ok(forms) => {
+            for form in forms {
+                println!("{}", form);
+            }
+        }
+        Err(err) => println!("{} {}", "Error:".color(error_color), err),
+    }
+}
+
+/// Evaluates the user's init script so its definitions are available at the
+/// prompt. A missing script is not an error.
+fn load_init_file(sauce: &mut Interpreter, path: &Path, error_color: Color) {
+    if !path.is_file() {
+        return;
+    }
+    if let Err(err) = sauce.eval_file(path) {
+        println!("{} {}: {}", "Error:".color(error_color), path.display(), err);
+    }
+}